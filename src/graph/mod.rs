@@ -0,0 +1,166 @@
+use std::{collections::HashMap, fmt};
+
+pub use node_set::NodeSet;
+
+mod node_set;
+
+#[derive(Debug)]
+pub enum GraphParseError {
+    InvalidEdge(String),
+    SelfLoop(String),
+}
+
+impl fmt::Display for GraphParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GraphParseError::InvalidEdge(line) => write!(f, "Invalid edge: {:?}", line),
+            GraphParseError::SelfLoop(node) => write!(f, "{} is linked to itself", node),
+        }
+    }
+}
+
+pub struct DegreeStats {
+    pub min: usize,
+    pub max: usize,
+    pub mean: f64,
+}
+
+// Node names are interned to ids 0..len, in order of appearance
+// Each node's neighbours are a bitset over these ids
+#[derive(Default)]
+pub struct UndirectedGraph {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    adjacency: Vec<NodeSet>,
+}
+
+impl UndirectedGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // One `a-b` edge per line, like the day 23 network map
+    pub fn parse_edge_list(
+        lines: impl Iterator<Item = String>,
+    ) -> Result<UndirectedGraph, GraphParseError> {
+        let mut graph = UndirectedGraph::new();
+
+        for line in lines {
+            let (a, b) = line
+                .split_once("-")
+                .filter(|(a, b)| !a.is_empty() && !b.is_empty())
+                .ok_or_else(|| GraphParseError::InvalidEdge(line.clone()))?;
+            if a == b {
+                return Err(GraphParseError::SelfLoop(a.to_string()));
+            }
+            graph.add_edge(a, b);
+        }
+
+        Ok(graph)
+    }
+
+    pub fn intern(&mut self, name: &str) -> usize {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+
+        let id = self.names.len();
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        self.adjacency.push(NodeSet::new(0));
+        id
+    }
+
+    pub fn add_edge(&mut self, a: &str, b: &str) {
+        let (a, b) = (self.intern(a), self.intern(b));
+        self.adjacency[a].insert(b);
+        self.adjacency[b].insert(a);
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn nb_edges(&self) -> usize {
+        self.adjacency.iter().map(NodeSet::len).sum::<usize>() / 2
+    }
+
+    pub fn name(&self, id: usize) -> &str {
+        &self.names[id]
+    }
+
+    pub fn names(&self, ids: &[usize]) -> Vec<&str> {
+        ids.iter().map(|id| self.name(*id)).collect()
+    }
+
+    pub fn neighbours(&self, id: usize) -> &NodeSet {
+        &self.adjacency[id]
+    }
+
+    pub fn degree(&self, id: usize) -> usize {
+        self.adjacency[id].len()
+    }
+
+    pub fn nodes(&self) -> NodeSet {
+        NodeSet::full(self.len())
+    }
+
+    pub fn degree_stats(&self) -> Option<DegreeStats> {
+        let degrees = (0..self.len())
+            .map(|id| self.degree(id))
+            .collect::<Vec<_>>();
+
+        Some(DegreeStats {
+            min: *degrees.iter().min()?,
+            max: *degrees.iter().max()?,
+            mean: degrees.iter().sum::<usize>() as f64 / degrees.len() as f64,
+        })
+    }
+
+    // Node ids of each component, largest first
+    pub fn connected_components(&self) -> Vec<Vec<usize>> {
+        let mut unvisited = self.nodes();
+        let mut components = Vec::new();
+
+        while let Some(start) = unvisited.first() {
+            unvisited.remove(start);
+            let mut component = vec![start];
+            let mut stack = vec![start];
+
+            while let Some(node) = stack.pop() {
+                for neighbour in self.adjacency[node].intersection(&unvisited).iter() {
+                    unvisited.remove(neighbour);
+                    component.push(neighbour);
+                    stack.push(neighbour);
+                }
+            }
+
+            component.sort();
+            components.push(component);
+        }
+
+        components.sort_by_key(|component| std::cmp::Reverse(component.len()));
+        components
+    }
+
+    // Graphviz description, each edge written once
+    pub fn to_dot(&self) -> String {
+        let mut dot = "graph {\n".to_string();
+
+        for (id, name) in self.names.iter().enumerate() {
+            dot.push_str(&format!("  \"{}\";\n", name));
+            for neighbour in self.adjacency[id]
+                .iter()
+                .filter(|neighbour| *neighbour > id)
+            {
+                dot.push_str(&format!(
+                    "  \"{}\" -- \"{}\";\n",
+                    name, self.names[neighbour]
+                ));
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
@@ -0,0 +1,95 @@
+// Set of node ids as a bitset, one bit per node of the graph
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct NodeSet {
+    words: Vec<u64>,
+}
+
+impl NodeSet {
+    pub fn new(nb_nodes: usize) -> Self {
+        NodeSet {
+            words: vec![0; nb_nodes.div_ceil(64)],
+        }
+    }
+
+    pub fn full(nb_nodes: usize) -> Self {
+        let mut set = NodeSet::new(nb_nodes);
+        (0..nb_nodes).for_each(|node| set.insert(node));
+        set
+    }
+
+    // Grows the set when needed, as nodes are interned while the graph is built
+    pub fn insert(&mut self, node: usize) {
+        if node / 64 >= self.words.len() {
+            self.words.resize(node / 64 + 1, 0);
+        }
+        self.words[node / 64] |= 1 << (node % 64);
+    }
+
+    pub fn remove(&mut self, node: usize) {
+        if let Some(word) = self.words.get_mut(node / 64) {
+            *word &= !(1 << (node % 64));
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|word| *word == 0)
+    }
+
+    fn combine(&self, other: &Self, combine_words: impl Fn(u64, u64) -> u64) -> Self {
+        let nb_words = self.words.len().max(other.words.len());
+        let word = |set: &Self, i: usize| set.words.get(i).copied().unwrap_or(0);
+
+        NodeSet {
+            words: (0..nb_words)
+                .map(|i| combine_words(word(self, i), word(other, i)))
+                .collect(),
+        }
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a & b)
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a & !b)
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a | b)
+    }
+
+    // Size of the intersection, without building it
+    pub fn intersection_len(&self, other: &Self) -> usize {
+        self.words
+            .iter()
+            .zip(&other.words)
+            .map(|(a, b)| (a & b).count_ones() as usize)
+            .sum()
+    }
+
+    pub fn first(&self) -> Option<usize> {
+        self.iter().next()
+    }
+
+    // Node ids in increasing order
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(i, word)| {
+            let mut word = *word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(i * 64 + bit)
+            })
+        })
+    }
+}
@@ -1,3 +1,4 @@
+mod graph;
 mod y2020;
 mod y2024;
 
@@ -10,18 +11,33 @@ pub fn read_input(year: u16, day: u8) -> io::Result<io::Lines<io::BufReader<File
     Ok(io::BufReader::new(file).lines())
 }
 
+// Value following the `name` flag in the options, e.g. `--step 12`
+pub fn option_value<'a>(options: &'a [String], name: &str) -> Option<&'a str> {
+    options
+        .iter()
+        .position(|option| option == name)
+        .map(|index| {
+            options
+                .get(index + 1)
+                .unwrap_or_else(|| panic!("{} expects a value", name))
+                .as_str()
+        })
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
 
     if args.len() < 4 {
-        eprintln!("Usage: cargo run -- [year] [day] [part]");
+        eprintln!("Usage: cargo run -- [year] [day] [part] [options]");
         eprintln!("Example: cargo run -- 2024 1 2");
+        eprintln!("Some days accept extra options, see their module");
         return;
     }
 
     let year = &args[1];
     let day = &args[2];
     let part = &args[3];
+    let options = &args[4..];
 
     match (year.as_str(), day.as_str(), part.as_str()) {
         ("2020", "1", "1") => y2020::day1::run_part_1(),
@@ -78,36 +94,43 @@ fn main() {
         ("2024", "7", "2") => y2024::day7::run_part_2(),
         ("2024", "8", "1") => y2024::day8::run_part_1(),
         ("2024", "8", "2") => y2024::day8::run_part_2(),
-        ("2024", "9", "1") => y2024::day9::run_part_1(),
-        ("2024", "9", "2") => y2024::day9::run_part_2(),
+        ("2024", "9", "1") => y2024::day9::run_part_1(options),
+        ("2024", "9", "2") => y2024::day9::run_part_2(options),
+        ("2024", "9", "compare") => y2024::day9::run_compare(options),
         ("2024", "10", "1") => y2024::day10::run_part_1(),
         ("2024", "10", "2") => y2024::day10::run_part_2(),
-        ("2024", "11", "1") => y2024::day11::run_part_1(),
-        ("2024", "11", "2") => y2024::day11::run_part_2(),
+        ("2024", "11", "1") => y2024::day11::run_part_1(options),
+        ("2024", "11", "2") => y2024::day11::run_part_2(options),
         ("2024", "12", "1") => y2024::day12::run_part_1(),
         ("2024", "12", "2") => y2024::day12::run_part_2(),
-        ("2024", "13", "1") => y2024::day13::run_part_1(),
-        ("2024", "13", "2") => y2024::day13::run_part_2(),
+        ("2024", "13", "1") => y2024::day13::run_part_1(options),
+        ("2024", "13", "2") => y2024::day13::run_part_2(options),
         ("2024", "14", "1") => y2024::day14::run_part_1(),
-        ("2024", "14", "2") => y2024::day14::run_part_2(),
-        ("2024", "15", "1") => y2024::day15::run_part_1(),
-        ("2024", "15", "2") => y2024::day15::run_part_2(),
-        ("2024", "16", "1") => y2024::day16::run_part_1(),
-        ("2024", "16", "2") => y2024::day16::run_part_2(),
+        ("2024", "14", "2") => y2024::day14::run_part_2(options),
+        ("2024", "14", "export") => y2024::day14::run_export(options),
+        ("2024", "15", "1") => y2024::day15::run_part_1(options),
+        ("2024", "15", "2") => y2024::day15::run_part_2(options),
+        ("2024", "15", "solve") => y2024::day15::run_solver(options),
+        ("2024", "16", "1") => y2024::day16::run_part_1(options),
+        ("2024", "16", "2") => y2024::day16::run_part_2(options),
         ("2024", "17", "1") => y2024::day17::run_part_1(),
         ("2024", "17", "2") => y2024::day17::run_part_2(),
-        ("2024", "18", "1") => y2024::day18::run_part_1(),
-        ("2024", "18", "2") => y2024::day18::run_part_2(),
+        ("2024", "18", "1") => y2024::day18::run_part_1(options),
+        ("2024", "18", "2") => y2024::day18::run_part_2(options),
         ("2024", "19", "1") => y2024::day19::run_part_1(),
         ("2024", "19", "2") => y2024::day19::run_part_2(),
-        ("2024", "20", "1") => y2024::day20::run_part_1(),
-        ("2024", "20", "2") => y2024::day20::run_part_2(),
+        ("2024", "20", "1") => y2024::day20::run_part_1(options),
+        ("2024", "20", "2") => y2024::day20::run_part_2(options),
         ("2024", "21", "1") => y2024::day21::run_part_1(),
         ("2024", "21", "2") => y2024::day21::run_part_2(),
+        ("2024", "21", "deep") => y2024::day21::run_deep(options),
         ("2024", "22", "1") => y2024::day22::run_part_1(),
-        ("2024", "22", "2") => y2024::day22::run_part_2(),
-        ("2024", "23", "1") => y2024::day23::run_part_1(),
+        ("2024", "22", "2") => y2024::day22::run_part_2(options),
+        ("2024", "22", "check") => y2024::day22::run_check(),
+        ("2024", "22", "seed") => y2024::day22::run_find_seed(options),
+        ("2024", "23", "1") => y2024::day23::run_part_1(options),
         ("2024", "23", "2") => y2024::day23::run_part_2(),
+        ("2024", "23", "stats") => y2024::day23::run_stats(options),
         ("2024", "24", "1") => y2024::day24::run_part_1(),
         ("2024", "24", "2") => y2024::day24::run_part_2(),
         ("2024", "25", "1") => y2024::day25::run_part_1(),
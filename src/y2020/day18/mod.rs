@@ -31,25 +31,25 @@ impl Expression {
 
         for (i, operation) in self.operations.iter().enumerate() {
             match operation {
-                &Operation::Multiplication => {
+                Operation::Multiplication => {
                     match &self.operands[i + 1] {
                         Operand::Expression(e) => expression_without_additions
                             .operands
                             .push(Operand::Value(e.evaluate_addition_first())),
                         Operand::Value(e) => expression_without_additions
                             .operands
-                            .push(Operand::Value(e.clone())),
+                            .push(Operand::Value(*e)),
                     }
                     expression_without_additions.operations.push(*operation);
                 }
-                &Operation::Addition => {
+                Operation::Addition => {
                     match expression_without_additions.operands.last_mut().unwrap() {
                         Operand::Value(v) => {
                             let to_add = match &self.operands[i + 1] {
                                 Operand::Expression(e) => e.evaluate_addition_first(),
                                 Operand::Value(v) => *v,
                             };
-                            *v = *v + to_add;
+                            *v += to_add;
                         }
                         _ => panic!("Last operand is not Value"),
                     };
@@ -68,8 +68,8 @@ impl Expression {
 
         for (operation, operand) in operations.iter().zip(&self.operands) {
             let v = match operand {
-                &Operand::Expression(ref e) => e.evaluate(),
-                &Operand::Value(v) => v,
+                Operand::Expression(e) => e.evaluate(),
+                Operand::Value(v) => *v,
             };
 
             match operation {
@@ -1,8 +1,12 @@
-use crate::read_input;
+use crate::{option_value, read_input};
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{self};
 
+use rules::RuleSet;
+
+mod rules;
+
 fn read_stones() -> Vec<usize> {
     let lines: io::Lines<io::BufReader<File>> = read_input(2024, 11).unwrap();
 
@@ -16,19 +20,12 @@ fn read_stones() -> Vec<usize> {
         .collect::<Vec<usize>>()
 }
 
-fn get_digit_count(mut n: usize) -> usize {
-    if n == 0 {
-        return 1;
-    }
-    let mut count = 0;
-    while n > 0 {
-        count += 1;
-        n /= 10;
-    }
-    count
-}
-
-fn handle_stone(stone: usize, blinks: usize, memo: &mut HashMap<(usize, usize), usize>) -> usize {
+fn handle_stone(
+    rules: &RuleSet,
+    stone: usize,
+    blinks: usize,
+    memo: &mut HashMap<(usize, usize), usize>,
+) -> usize {
     if blinks == 0 {
         return 1;
     }
@@ -36,44 +33,86 @@ fn handle_stone(stone: usize, blinks: usize, memo: &mut HashMap<(usize, usize),
     if let Some(known_result) = memo.get(&(stone, blinks)) {
         *known_result
     } else {
-        let unknown_result = if stone == 0 {
-            handle_stone(1, blinks - 1, memo)
-        } else {
-            let digits_count = get_digit_count(stone);
-            if digits_count % 2 == 0 {
-                let half = digits_count / 2;
-                let divisor = 10_usize.pow(half as u32);
-                let right = stone % divisor;
-                let left = stone / divisor;
-
-                return handle_stone(right, blinks - 1, memo)
-                    + handle_stone(left, blinks - 1, memo);
-            } else {
-                handle_stone(stone * 2024, blinks - 1, memo)
-            }
-        };
+        let unknown_result = rules
+            .blink(stone)
+            .into_iter()
+            .map(|stone| handle_stone(rules, stone, blinks - 1, memo))
+            .sum();
 
         memo.entry((stone, blinks)).or_insert(unknown_result);
         unknown_result
     }
 }
 
-fn run(blinks: usize) {
+fn count_stones_recursive(rules: &RuleSet, stones: &[usize], blinks: usize) -> usize {
     let mut memo: HashMap<(usize, usize), usize> = HashMap::new();
+
+    stones
+        .iter()
+        .map(|stone| handle_stone(rules, *stone, blinks, &mut memo))
+        .sum()
+}
+
+// Stones with the same value evolve the same way, so only their count per value is kept
+fn blink_histogram(rules: &RuleSet, histogram: &HashMap<usize, usize>) -> HashMap<usize, usize> {
+    let mut next_histogram = HashMap::new();
+
+    for (stone, count) in histogram {
+        for next_stone in rules.blink(*stone) {
+            *next_histogram.entry(next_stone).or_default() += count;
+        }
+    }
+
+    next_histogram
+}
+
+// Number of stones and of distinct stone values after each blink, from blink 0
+fn evolve_histogram(rules: &RuleSet, stones: &[usize], blinks: usize) -> Vec<(usize, usize)> {
+    let mut histogram: HashMap<usize, usize> = HashMap::new();
+    for stone in stones {
+        *histogram.entry(*stone).or_default() += 1;
+    }
+
+    let mut stats = vec![(stones.len(), histogram.len())];
+    for _ in 0..blinks {
+        histogram = blink_histogram(rules, &histogram);
+        stats.push((histogram.values().sum(), histogram.len()));
+    }
+
+    stats
+}
+
+// --rules RULES blinks with other rules, see RuleSet::parse
+// --engine recursive|histogram picks how the stones are counted, recursive by default
+// --stats prints the number of stones and of distinct values after each blink
+fn run(blinks: usize, options: &[String]) {
+    let rules = option_value(options, "--rules").map_or_else(RuleSet::puzzle, |rules| {
+        RuleSet::parse(rules).unwrap_or_else(|error| panic!("{}", error))
+    });
+    let engine = option_value(options, "--engine").unwrap_or("recursive");
     let stones = read_stones();
 
-    let count = stones
-        .into_iter()
-        .map(|stone| handle_stone(stone, blinks, &mut memo))
-        .sum::<usize>();
+    let count = match engine {
+        "recursive" => count_stones_recursive(&rules, &stones, blinks),
+        "histogram" => evolve_histogram(&rules, &stones, blinks).last().unwrap().0,
+        _ => panic!("--engine expects recursive or histogram"),
+    };
+
+    if options.iter().any(|option| option == "--stats") {
+        for (blink, (nb_stones, nb_distinct)) in
+            evolve_histogram(&rules, &stones, blinks).iter().enumerate()
+        {
+            println!("Blink {blink}: {nb_stones} stones, {nb_distinct} distinct");
+        }
+    }
 
     println!("{}", count);
 }
 
-pub fn run_part_1() {
-    run(25);
+pub fn run_part_1(options: &[String]) {
+    run(25, options);
 }
 
-pub fn run_part_2() {
-    run(75);
+pub fn run_part_2(options: &[String]) {
+    run(75, options);
 }
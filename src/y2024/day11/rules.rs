@@ -0,0 +1,174 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum RuleParseError {
+    MissingArrow(String),
+    InvalidCondition(String),
+    InvalidTransformation(String),
+}
+
+impl fmt::Display for RuleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleParseError::MissingArrow(rule) => write!(f, "rule {:?} has no =>", rule),
+            RuleParseError::InvalidCondition(condition) => {
+                write!(f, "invalid condition {:?}", condition)
+            }
+            RuleParseError::InvalidTransformation(transformation) => {
+                write!(f, "invalid transformation {:?}", transformation)
+            }
+        }
+    }
+}
+
+pub fn get_digit_count(mut n: usize) -> usize {
+    if n == 0 {
+        return 1;
+    }
+    let mut count = 0;
+    while n > 0 {
+        count += 1;
+        n /= 10;
+    }
+    count
+}
+
+pub enum Condition {
+    Equals(usize),
+    EvenDigits,
+    OddDigits,
+    Always,
+}
+
+impl Condition {
+    fn matches(&self, stone: usize) -> bool {
+        match self {
+            Condition::Equals(value) => stone == *value,
+            Condition::EvenDigits => get_digit_count(stone).is_multiple_of(2),
+            Condition::OddDigits => !get_digit_count(stone).is_multiple_of(2),
+            Condition::Always => true,
+        }
+    }
+
+    fn parse(condition: &str) -> Result<Condition, RuleParseError> {
+        let invalid = || RuleParseError::InvalidCondition(condition.to_string());
+
+        match condition {
+            "even-digits" => Ok(Condition::EvenDigits),
+            "odd-digits" => Ok(Condition::OddDigits),
+            "*" => Ok(Condition::Always),
+            _ => {
+                let value = condition.strip_prefix("=").ok_or_else(invalid)?;
+                Ok(Condition::Equals(value.parse().map_err(|_| invalid())?))
+            }
+        }
+    }
+}
+
+pub enum Transformation {
+    Replace(usize),
+    // Left and right halves of the digits, leading zeros dropped
+    SplitDigits,
+    Multiply(usize),
+    Add(usize),
+}
+
+impl Transformation {
+    fn apply(&self, stone: usize) -> Vec<usize> {
+        match self {
+            Transformation::Replace(value) => vec![*value],
+            Transformation::SplitDigits => {
+                let divisor = 10_usize.pow((get_digit_count(stone) / 2) as u32);
+                vec![stone / divisor, stone % divisor]
+            }
+            Transformation::Multiply(factor) => vec![stone
+                .checked_mul(*factor)
+                .expect("The stone value overflowed")],
+            Transformation::Add(term) => {
+                vec![stone
+                    .checked_add(*term)
+                    .expect("The stone value overflowed")]
+            }
+        }
+    }
+
+    fn parse(transformation: &str) -> Result<Transformation, RuleParseError> {
+        let invalid = || RuleParseError::InvalidTransformation(transformation.to_string());
+        if transformation == "split" {
+            return Ok(Transformation::SplitDigits);
+        }
+
+        let (operator, value) = transformation.split_at_checked(1).ok_or_else(invalid)?;
+        let value = value.parse::<usize>().map_err(|_| invalid())?;
+        match operator {
+            "=" => Ok(Transformation::Replace(value)),
+            "*" => Ok(Transformation::Multiply(value)),
+            "+" => Ok(Transformation::Add(value)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+pub struct Rule {
+    condition: Condition,
+    transformation: Transformation,
+}
+
+// The first rule whose condition matches the stone transforms it
+// A stone matching no rule stays as it is
+pub struct RuleSet {
+    rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    // The puzzle rules: 0 becomes 1, even digit counts split, anything else is multiplied by 2024
+    pub fn puzzle() -> RuleSet {
+        RuleSet {
+            rules: vec![
+                Rule {
+                    condition: Condition::Equals(0),
+                    transformation: Transformation::Replace(1),
+                },
+                Rule {
+                    condition: Condition::EvenDigits,
+                    transformation: Transformation::SplitDigits,
+                },
+                Rule {
+                    condition: Condition::Always,
+                    transformation: Transformation::Multiply(2024),
+                },
+            ],
+        }
+    }
+
+    // Rules separated by `;`, each as `condition => transformation`
+    // Conditions: =N, even-digits, odd-digits, * (any stone)
+    // Transformations: =N, split, *N, +N
+    // The puzzle rules are "=0 => =1; even-digits => split; * => *2024"
+    pub fn parse(rules: &str) -> Result<RuleSet, RuleParseError> {
+        let rules = rules
+            .split(';')
+            .map(str::trim)
+            .filter(|rule| !rule.is_empty())
+            .map(|rule| {
+                let (condition, transformation) = rule
+                    .split_once("=>")
+                    .ok_or_else(|| RuleParseError::MissingArrow(rule.to_string()))?;
+                Ok(Rule {
+                    condition: Condition::parse(condition.trim())?,
+                    transformation: Transformation::parse(transformation.trim())?,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(RuleSet { rules })
+    }
+
+    // The stones replacing the given one after a blink
+    pub fn blink(&self, stone: usize) -> Vec<usize> {
+        self.rules
+            .iter()
+            .find(|rule| rule.condition.matches(stone))
+            .map_or_else(|| vec![stone], |rule| rule.transformation.apply(stone))
+    }
+}
@@ -122,10 +122,10 @@ impl Garden {
                     None
                 };
 
-                let left_same_label = left.map_or(false, |pos| grid[pos.0][pos.1].label == label);
-                let top_same_label = top.map_or(false, |pos| grid[pos.0][pos.1].label == label);
-                let down_same_label = down.map_or(false, |pos| grid[pos.0][pos.1].label == label);
-                let right_same_label = right.map_or(false, |pos| grid[pos.0][pos.1].label == label);
+                let left_same_label = left.is_some_and(|pos| grid[pos.0][pos.1].label == label);
+                let top_same_label = top.is_some_and(|pos| grid[pos.0][pos.1].label == label);
+                let down_same_label = down.is_some_and(|pos| grid[pos.0][pos.1].label == label);
+                let right_same_label = right.is_some_and(|pos| grid[pos.0][pos.1].label == label);
 
                 corners += [
                     // Inner corners
@@ -2,10 +2,23 @@ use crate::read_input;
 use std::fs::File;
 use std::io::{self};
 
+// i128 keeps the determinants exact even with the part 2 prize offset
 #[derive(Debug)]
 struct Coordinate {
-    x: usize,
-    y: usize,
+    x: i128,
+    y: i128,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Presses {
+    a: i128,
+    b: i128,
+}
+
+impl Presses {
+    fn tokens(&self) -> i128 {
+        3 * self.a + self.b
+    }
 }
 
 struct ClawConfiguration {
@@ -14,28 +27,99 @@ struct ClawConfiguration {
     prize: Coordinate,
 }
 
+// (g, x, y) with g = gcd(u, v) = u * x + v * y
+fn extended_gcd(u: i128, v: i128) -> (i128, i128, i128) {
+    if v == 0 {
+        return (u, 1, 0);
+    }
+    let (g, x, y) = extended_gcd(v, u.rem_euclid(v));
+    (g, y, x - u.div_euclid(v) * y)
+}
+
+// Cheapest non-negative solution of a * u + b * v = w, with u, v and w non-negative
+fn solve_on_line(u: i128, v: i128, w: i128) -> Option<Presses> {
+    match (u, v) {
+        (0, 0) => (w == 0).then_some(Presses { a: 0, b: 0 }),
+        // a button that doesn't move the claw is never pressed
+        (_, 0) => (w % u == 0).then_some(Presses { a: w / u, b: 0 }),
+        (0, _) => (w % v == 0).then_some(Presses { a: 0, b: w / v }),
+        _ => {
+            let (g, x, y) = extended_gcd(u, v);
+            if w % g != 0 {
+                return None;
+            }
+
+            // every solution is (a0 + k * v / g, b0 - k * u / g)
+            let (a0, b0) = (x * (w / g), y * (w / g));
+            let (a_step, b_step) = (v / g, u / g);
+            // a >= 0 and b >= 0 bound k
+            let min_k = -(a0.div_euclid(a_step));
+            let max_k = b0.div_euclid(b_step);
+            if min_k > max_k {
+                return None;
+            }
+
+            // the tokens are linear in k, the cheapest solution is at one end
+            let k = if 3 * a_step > b_step { min_k } else { max_k };
+            Some(Presses {
+                a: a0 + k * a_step,
+                b: b0 - k * b_step,
+            })
+        }
+    }
+}
+
 impl ClawConfiguration {
-    fn compute_determinant(v1: &Coordinate, v2: &Coordinate) -> f64 {
-        v1.x as f64 * v2.y as f64 - v1.y as f64 * v2.x as f64
+    fn compute_determinant(v1: &Coordinate, v2: &Coordinate) -> i128 {
+        v1.x * v2.y - v1.y * v2.x
     }
 
-    pub fn adjust_prize(&mut self, prize_adjustment: usize) {
+    pub fn adjust_prize(&mut self, prize_adjustment: i128) {
         self.prize.x += prize_adjustment;
         self.prize.y += prize_adjustment;
     }
 
-    // Using Cramer's Rule
-    pub fn count_tokens(&self) -> f64 {
+    // Using Cramer's Rule, in exact integer arithmetic
+    // When the buttons are collinear, the cheapest of the many solutions is picked
+    pub fn solve(&self) -> Option<Presses> {
         let d = Self::compute_determinant(&self.a, &self.b);
+
+        if d == 0 {
+            return self.solve_collinear();
+        }
+
         let d_a = Self::compute_determinant(&self.prize, &self.b);
         let d_b = Self::compute_determinant(&self.a, &self.prize);
+        if d_a % d != 0 || d_b % d != 0 {
+            return None;
+        }
+
+        let presses = Presses {
+            a: d_a / d,
+            b: d_b / d,
+        };
+        (presses.a >= 0 && presses.b >= 0).then_some(presses)
+    }
+
+    // Both buttons move along the same line, so the prize must be on it too
+    // Then one axis is enough, as long as the line isn't perpendicular to it
+    fn solve_collinear(&self) -> Option<Presses> {
+        if Self::compute_determinant(&self.a, &self.prize) != 0
+            || Self::compute_determinant(&self.b, &self.prize) != 0
+        {
+            return None;
+        }
 
-        (d_a / d) * 3.0 + (d_b / d)
+        if self.a.x != 0 || self.b.x != 0 {
+            solve_on_line(self.a.x, self.b.x, self.prize.x)
+        } else {
+            solve_on_line(self.a.y, self.b.y, self.prize.y)
+        }
     }
 }
 
-fn parse_coordinate(s: &str, delimiter: &str) -> usize {
-    s.split_once(delimiter).unwrap().1.parse::<usize>().unwrap()
+fn parse_coordinate(s: &str, delimiter: &str) -> i128 {
+    s.split_once(delimiter).unwrap().1.parse::<i128>().unwrap()
 }
 
 fn extract_button_coordinates(s: &str, delimiter: &str) -> Coordinate {
@@ -65,25 +149,42 @@ fn read_configurations() -> Vec<ClawConfiguration> {
         .collect::<Vec<_>>()
 }
 
-pub fn run(prize_adjustment: usize) -> usize {
+// --verbose prints the presses of each machine
+pub fn run(prize_adjustment: i128, options: &[String]) -> i128 {
+    let is_verbose = options.iter().any(|option| option == "--verbose");
+
     read_configurations()
         .into_iter()
-        .map(|mut config| {
+        .enumerate()
+        .filter_map(|(i, mut config)| {
             config.adjust_prize(prize_adjustment);
-            config
+            let presses = config.solve();
+
+            if is_verbose {
+                match presses {
+                    Some(presses) => println!(
+                        "Machine {}: A x {}, B x {}, {} tokens",
+                        i + 1,
+                        presses.a,
+                        presses.b,
+                        presses.tokens()
+                    ),
+                    None => println!("Machine {}: no solution", i + 1),
+                }
+            }
+
+            presses
         })
-        .map(|config| config.count_tokens())
-        .filter(|count| count.floor() == *count)
-        .map(|count| count as usize)
-        .sum::<usize>()
+        .map(|presses| presses.tokens())
+        .sum::<i128>()
 }
 
-pub fn run_part_1() {
-    let count = run(0);
+pub fn run_part_1(options: &[String]) {
+    let count = run(0, options);
     println!("{count}");
 }
 
-pub fn run_part_2() {
-    let count = run(10000000000000);
+pub fn run_part_2(options: &[String]) {
+    let count = run(10000000000000, options);
     println!("{count}");
 }
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+use super::{Robot, COLS, ROWS};
+
+// How clustered the robots are along one axis, the lower the more clustered
+#[derive(Clone, Copy)]
+pub enum AxisMetric {
+    Variance,
+    // Shannon entropy of the robots distribution over the axis
+    Entropy,
+}
+
+impl AxisMetric {
+    pub fn parse(metric: &str) -> Option<AxisMetric> {
+        match metric {
+            "variance" => Some(AxisMetric::Variance),
+            "entropy" => Some(AxisMetric::Entropy),
+            _ => None,
+        }
+    }
+
+    fn measure(&self, values: &[i32]) -> f64 {
+        let nb_values = values.len() as f64;
+
+        match self {
+            AxisMetric::Variance => {
+                let mean = values.iter().sum::<i32>() as f64 / nb_values;
+                values
+                    .iter()
+                    .map(|value| (*value as f64 - mean).powi(2))
+                    .sum::<f64>()
+                    / nb_values
+            }
+            AxisMetric::Entropy => {
+                let mut counts: HashMap<i32, usize> = HashMap::new();
+                for value in values {
+                    *counts.entry(*value).or_default() += 1;
+                }
+                counts
+                    .values()
+                    .map(|count| {
+                        let p = *count as f64 / nb_values;
+                        -p * p.log2()
+                    })
+                    .sum()
+            }
+        }
+    }
+}
+
+// Second in 0..period where the axis values are the most clustered
+fn most_clustered_second(
+    robots: &[Robot],
+    metric: AxisMetric,
+    period: i32,
+    axis_value: impl Fn(&Robot, usize) -> i32,
+) -> i32 {
+    (0..period)
+        .map(|second| {
+            let values = robots
+                .iter()
+                .map(|robot| axis_value(robot, second as usize))
+                .collect::<Vec<_>>();
+            (second, metric.measure(&values))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .unwrap()
+        .0
+}
+
+// Columns repeat every COLS seconds and rows every ROWS seconds, so each axis is
+// looked at separately over its own period, and the two are combined with the CRT
+pub fn find_easter_egg_second(robots: &[Robot], metric: AxisMetric) -> usize {
+    let col_second = most_clustered_second(robots, metric, COLS, |robot, s| robot.position_at(s).1);
+    let row_second = most_clustered_second(robots, metric, ROWS, |robot, s| robot.position_at(s).0);
+
+    // COLS and ROWS are coprime, one of these is congruent to row_second modulo ROWS
+    (0..ROWS)
+        .map(|k| col_second + k * COLS)
+        .find(|second| second % ROWS == row_second)
+        .unwrap() as usize
+}
@@ -0,0 +1,57 @@
+use super::{Robot, COLS, ROWS};
+
+pub enum FrameFormat {
+    // One bit per tile, set when at least one robot is there
+    Pbm,
+    // Grey levels by number of robots on the tile
+    Pgm,
+}
+
+impl FrameFormat {
+    pub fn parse(format: &str) -> Option<FrameFormat> {
+        match format {
+            "pbm" => Some(FrameFormat::Pbm),
+            "pgm" => Some(FrameFormat::Pgm),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &str {
+        match self {
+            FrameFormat::Pbm => "pbm",
+            FrameFormat::Pgm => "pgm",
+        }
+    }
+}
+
+// Plain text netpbm image of the robots after the given number of seconds
+pub fn render_frame(robots: &[Robot], seconds: usize, format: &FrameFormat) -> String {
+    let mut counts = vec![vec![0usize; COLS as usize]; ROWS as usize];
+    for robot in robots {
+        let (row, col) = robot.position_at(seconds);
+        counts[row as usize][col as usize] += 1;
+    }
+
+    let header = match format {
+        FrameFormat::Pbm => format!("P1\n{} {}\n", COLS, ROWS),
+        FrameFormat::Pgm => {
+            let max_count = counts.iter().flatten().max().copied().unwrap_or(0).max(1);
+            format!("P2\n{} {}\n{}\n", COLS, ROWS, max_count)
+        }
+    };
+
+    header
+        + &counts
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|count| match format {
+                        FrameFormat::Pbm => usize::from(*count > 0).to_string(),
+                        FrameFormat::Pgm => count.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    + "\n"
+            })
+            .collect::<String>()
+}
@@ -1,4 +1,12 @@
-use crate::read_input;
+use std::fs;
+
+use detector::{find_easter_egg_second, AxisMetric};
+use frame::{render_frame, FrameFormat};
+
+use crate::{option_value, read_input};
+
+mod detector;
+mod frame;
 
 const COLS: i32 = 101;
 const ROWS: i32 = 103;
@@ -11,6 +19,19 @@ struct Robot {
     col_velocity: i32,
 }
 
+impl Robot {
+    // (row, col) after the given number of seconds, wrapping around the bathroom
+    // Each axis repeats with its own period, reducing the seconds first avoids overflows
+    fn position_at(&self, seconds: usize) -> (i32, i32) {
+        let row_seconds = (seconds % ROWS as usize) as i32;
+        let col_seconds = (seconds % COLS as usize) as i32;
+        (
+            (self.row + row_seconds * self.row_velocity).rem_euclid(ROWS),
+            (self.col + col_seconds * self.col_velocity).rem_euclid(COLS),
+        )
+    }
+}
+
 struct BathroomSecurity {
     robots: Vec<Robot>,
 }
@@ -26,21 +47,7 @@ impl BathroomSecurity {
 
     pub fn simulate(&mut self, seconds: usize) {
         self.robots.iter_mut().for_each(|robot| {
-            let c = robot.col + (seconds as i32 * robot.col_velocity);
-            let r = robot.row + (seconds as i32 * robot.row_velocity);
-
-            let mut c = c % COLS;
-            let mut r = r % ROWS;
-
-            if c < 0 {
-                c += COLS
-            };
-            if r < 0 {
-                r += ROWS
-            };
-
-            robot.col = c;
-            robot.row = r;
+            (robot.row, robot.col) = robot.position_at(seconds);
         });
     }
 
@@ -138,16 +145,52 @@ pub fn run_part_1() {
     println!("{safety_factor}");
 }
 
-pub fn run_part_2() {
-    for seconds in 0..10000 {
-        let mut bathroom_security = build_bathroom_security();
-        bathroom_security.simulate(seconds);
+// Looks for a long run of robots on a row, one second at a time
+fn find_tree_by_rendering() -> Option<usize> {
+    let mut bathroom_security = build_bathroom_security();
 
+    for seconds in 0..10000 {
         if bathroom_security.check_has_tree() {
-            println!("{}", bathroom_security.print_debug());
-            println!();
-            println!("Solution = {seconds}");
-            break;
+            return Some(seconds);
         }
+        bathroom_security.simulate(1);
     }
+
+    None
+}
+
+// --detector variance|entropy|render picks how the tree is found, variance by default
+pub fn run_part_2(options: &[String]) {
+    let detector = option_value(options, "--detector").unwrap_or("variance");
+
+    let seconds = if detector == "render" {
+        find_tree_by_rendering().expect("No tree found")
+    } else {
+        let metric =
+            AxisMetric::parse(detector).expect("--detector expects variance, entropy or render");
+        find_easter_egg_second(&build_bathroom_security().robots, metric)
+    };
+
+    let mut bathroom_security = build_bathroom_security();
+    bathroom_security.simulate(seconds);
+
+    println!("{}", bathroom_security.print_debug());
+    println!();
+    println!("Solution = {seconds}");
+}
+
+// Writes the robots after SECONDS seconds to frame_SECONDS.pbm or .pgm, pbm by default
+pub fn run_export(options: &[String]) {
+    let seconds = options
+        .first()
+        .and_then(|seconds| seconds.parse::<usize>().ok())
+        .expect("export expects a number of seconds");
+    let format = options.get(1).map_or(FrameFormat::Pbm, |format| {
+        FrameFormat::parse(format).expect("The frame format is pbm or pgm")
+    });
+
+    let robots = build_bathroom_security().robots;
+    let path = format!("src/y2024/day14/frame_{}.{}", seconds, format.extension());
+    fs::write(&path, render_frame(&robots, seconds, &format)).expect("Failed to write frame");
+    println!("{}", path);
 }
@@ -1,8 +1,7 @@
 use std::{fmt::Debug, io::Write};
 
-use super::warehouse::Move;
+use super::warehouse::{Move, Position};
 
-#[allow(dead_code)]
 const DEBUG_FILE: &str = "src/y2024/day15/debug_output.txt";
 
 impl Debug for Move {
@@ -17,20 +16,37 @@ impl Debug for Move {
     }
 }
 
+// One recorded warehouse state. The first frame is the initial state and has no move.
+pub struct Frame {
+    pub r#move: Option<Move>,
+    pub state: String,
+    // Cells occupied by each box pushed during the move, before it was pushed
+    pub pushed_boxes: Vec<Vec<Position>>,
+}
+
 #[derive(Default)]
 pub struct WarehouseDebugger {
     pub is_activated: bool,
     pub debug_file: Option<std::fs::File>,
+    pub frames: Vec<Frame>,
+    pending_pushed_boxes: Vec<Vec<Position>>,
 }
 
 impl WarehouseDebugger {
-    #[allow(dead_code)]
     pub fn activate_debug(&mut self) {
         self.is_activated = true;
         self.debug_file =
             Some(std::fs::File::create(DEBUG_FILE).expect("Failed to create debug file"));
     }
 
+    pub fn activate_recording(&mut self) {
+        self.is_activated = true;
+    }
+
+    pub fn record_pushed_box(&mut self, cells: Vec<Position>) {
+        self.pending_pushed_boxes.push(cells);
+    }
+
     pub fn debug_move(&mut self, r#move: &Move, new_state: &str) {
         if let Some(file) = &mut self.debug_file {
             writeln!(file, "Move {:?}", r#move).expect("Failed to write move");
@@ -38,6 +54,12 @@ impl WarehouseDebugger {
             writeln!(file, "-----------------------").expect("Failed to write separator");
             writeln!(file).expect("Failed to write final newline");
         }
+
+        self.frames.push(Frame {
+            r#move: Some(r#move.clone()),
+            state: new_state.to_string(),
+            pushed_boxes: std::mem::take(&mut self.pending_pushed_boxes),
+        });
     }
 
     pub fn debug_initial_state(&mut self, state: &str) {
@@ -46,5 +68,11 @@ impl WarehouseDebugger {
             writeln!(file, "-----------------------").expect("Failed to write separator");
             writeln!(file).expect("Failed to write final newline");
         }
+
+        self.frames.push(Frame {
+            r#move: None,
+            state: state.to_string(),
+            pushed_boxes: vec![],
+        });
     }
 }
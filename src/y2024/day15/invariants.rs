@@ -0,0 +1,119 @@
+use std::fmt;
+
+use super::warehouse::{Cell, Position, Warehouse};
+
+#[derive(Debug)]
+pub enum InvariantViolation {
+    RobotNotOnEmptyCell(Position),
+    // A BoxLeft not closed by a BoxRight on the same row
+    UnclosedBox(Position),
+    // A BoxMiddle or BoxRight without a BoxLeft before it
+    StrayBoxPart(Position),
+    BoxWidthChanged {
+        position: Position,
+        width: usize,
+        expected: usize,
+    },
+    BoxCountChanged {
+        count: usize,
+        expected: usize,
+    },
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvariantViolation::RobotNotOnEmptyCell(Position(row, col)) => {
+                write!(f, "robot at {} {} is not on an empty cell", row, col)
+            }
+            InvariantViolation::UnclosedBox(Position(row, col)) => {
+                write!(f, "box starting at {} {} is not closed", row, col)
+            }
+            InvariantViolation::StrayBoxPart(Position(row, col)) => {
+                write!(f, "box part at {} {} does not belong to a box", row, col)
+            }
+            InvariantViolation::BoxWidthChanged {
+                position: Position(row, col),
+                width,
+                expected,
+            } => write!(
+                f,
+                "box at {} {} is {} wide instead of {}",
+                row, col, width, expected
+            ),
+            InvariantViolation::BoxCountChanged { count, expected } => {
+                write!(f, "found {} boxes instead of {}", count, expected)
+            }
+        }
+    }
+}
+
+impl Warehouse {
+    pub fn count_boxes(&self) -> usize {
+        self.grid
+            .iter()
+            .flatten()
+            .filter(|cell| **cell == Cell::Box || **cell == Cell::BoxLeft)
+            .count()
+    }
+
+    pub fn check_invariants(&self, expected_box_count: usize) -> Result<(), InvariantViolation> {
+        let robot = &self.robot.position;
+        if self.grid[robot.0][robot.1] != Cell::Empty {
+            return Err(InvariantViolation::RobotNotOnEmptyCell(robot.clone()));
+        }
+
+        let mut expected_width = None;
+        for (row, cells) in self.grid.iter().enumerate() {
+            // Start column of the wide box being scanned
+            let mut open_box: Option<usize> = None;
+
+            for (col, cell) in cells.iter().enumerate() {
+                let width = match (cell, open_box) {
+                    (Cell::Box, None) => Some(1),
+                    (Cell::BoxLeft, None) => {
+                        open_box = Some(col);
+                        None
+                    }
+                    (Cell::BoxMiddle, Some(_)) => None,
+                    (Cell::BoxRight, Some(start)) => {
+                        open_box = None;
+                        Some(col - start + 1)
+                    }
+                    (Cell::BoxMiddle | Cell::BoxRight, None) => {
+                        return Err(InvariantViolation::StrayBoxPart(Position(row, col)));
+                    }
+                    (_, Some(start)) => {
+                        return Err(InvariantViolation::UnclosedBox(Position(row, start)));
+                    }
+                    (_, None) => None,
+                };
+
+                if let Some(width) = width {
+                    let expected = *expected_width.get_or_insert(width);
+                    if width != expected {
+                        return Err(InvariantViolation::BoxWidthChanged {
+                            position: Position(row, col + 1 - width),
+                            width,
+                            expected,
+                        });
+                    }
+                }
+            }
+
+            if let Some(start) = open_box {
+                return Err(InvariantViolation::UnclosedBox(Position(row, start)));
+            }
+        }
+
+        let count = self.count_boxes();
+        if count != expected_box_count {
+            return Err(InvariantViolation::BoxCountChanged {
+                count,
+                expected: expected_box_count,
+            });
+        }
+
+        Ok(())
+    }
+}
@@ -1,19 +1,129 @@
 mod debugger;
+mod invariants;
 mod parser;
+mod replay;
+mod solver;
 mod warehouse;
 
-use parser::init_warehouse;
+use std::{fs, io};
 
-pub fn run_part_1() {
+use parser::{init_warehouse, parse_moves, parse_warehouse};
+use replay::ReplayViewer;
+use solver::{box_anchors, SokobanSolver};
+use warehouse::{Position, Warehouse};
+
+const LEVEL_FILE: &str = "src/y2024/day15/level.txt";
+
+fn init_wide_warehouse() -> Warehouse {
     let mut warehouse = init_warehouse();
-    warehouse.apply_robot_moves();
+    warehouse.make_wide(2);
+    warehouse
+}
+
+// Undoing every move must bring the warehouse back to its initial state
+fn check_undo(warehouse: &mut Warehouse, initial: &Warehouse) {
+    while warehouse.undo_move().is_some() {}
+    if !warehouse.has_same_state(initial) {
+        eprintln!("Undoing every move did not restore the initial state");
+    }
+}
+
+// --debug dumps every state to the debug file, --replay opens the viewer once moves are applied,
+// --check verifies the warehouse invariants after every move and that moves can be undone
+fn run(init: fn() -> Warehouse, options: &[String]) {
+    let mut warehouse = init();
+    let is_replay = options.iter().any(|option| option == "--replay");
+    let is_check = options.iter().any(|option| option == "--check");
+    if options.iter().any(|option| option == "--debug") {
+        warehouse.debugger.activate_debug();
+    }
+    if is_replay {
+        warehouse.debugger.activate_recording();
+    }
+
+    if is_check {
+        if let Err((index, violation)) = warehouse.apply_robot_moves_checked() {
+            eprintln!("Invariant broken after move {}: {}", index, violation);
+        }
+    } else {
+        warehouse.apply_robot_moves();
+    }
+
+    if is_replay {
+        ReplayViewer::new(&warehouse.debugger.frames).run(io::stdin().lock(), &mut io::stdout());
+    }
+
     println!("{}", warehouse.compute_gps_coordinates_sum());
+
+    if is_check {
+        check_undo(&mut warehouse, &init());
+    }
 }
 
-pub fn run_part_2() {
-    let mut warehouse = init_warehouse();
-    warehouse.make_wide();
-    warehouse.debugger.activate_debug();
+pub fn run_part_1(options: &[String]) {
+    run(init_warehouse, options);
+}
+
+pub fn run_part_2(options: &[String]) {
+    run(init_wide_warehouse, options);
+}
+
+// A level is a warehouse grid where `x` marks a target and `*` a box already on one
+fn parse_level(level: &str) -> (Warehouse, Vec<Position>) {
+    let mut targets = vec![];
+    let lines = level
+        .lines()
+        .enumerate()
+        .map(|(row, line)| {
+            line.chars()
+                .enumerate()
+                .map(|(col, c)| match c {
+                    'x' => {
+                        targets.push(Position(row, col));
+                        '.'
+                    }
+                    '*' => {
+                        targets.push(Position(row, col));
+                        'O'
+                    }
+                    c => c,
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>();
+
+    (parse_warehouse(lines.into_iter()), targets)
+}
+
+// Solves the level file given as first option, or the default one. --wide doubles it like part 2.
+pub fn run_solver(options: &[String]) {
+    let path = options
+        .iter()
+        .find(|option| !option.starts_with("--"))
+        .map_or(LEVEL_FILE, |path| path.as_str());
+    let level = fs::read_to_string(path).expect("Failed to read level file");
+    let (mut warehouse, mut targets) = parse_level(&level);
+
+    if options.iter().any(|option| option == "--wide") {
+        warehouse.make_wide(2);
+        for target in targets.iter_mut() {
+            target.1 *= 2;
+        }
+    }
+
+    let Some(solution) = SokobanSolver::new(&mut warehouse, &targets).solve() else {
+        println!("No solution");
+        return;
+    };
+    println!("{}", solution);
+
+    // Replay the solution through the regular simulation to verify it
+    warehouse.robot.moves = parse_moves(&solution);
     warehouse.apply_robot_moves();
-    println!("{}", warehouse.compute_gps_coordinates_sum());
+    let boxes = box_anchors(&warehouse);
+    if targets.iter().all(|target| boxes.contains(target)) {
+        println!("Verified in {} moves", solution.len());
+    } else {
+        eprintln!("Replaying the solution does not put every box on a target");
+    }
 }
@@ -15,7 +15,7 @@ fn parse_cells(line: &str) -> Vec<Cell> {
         .collect::<Vec<_>>()
 }
 
-fn parse_moves(line: &str) -> Vec<Move> {
+pub fn parse_moves(line: &str) -> Vec<Move> {
     line.chars()
         .map(|c| match c {
             '<' => Move::LEFT,
@@ -27,9 +27,21 @@ fn parse_moves(line: &str) -> Vec<Move> {
         .collect::<Vec<_>>()
 }
 
-pub fn init_warehouse() -> Warehouse {
+pub fn format_moves(moves: &[Move]) -> String {
+    moves
+        .iter()
+        .map(|r#move| match *r#move {
+            Move::LEFT => '<',
+            Move::RIGHT => '>',
+            Move::UP => '^',
+            Move::DOWN => 'v',
+            ref invalid => panic!("Invalid move found while formatting: {:?}", invalid),
+        })
+        .collect()
+}
+
+pub fn parse_warehouse(lines: impl Iterator<Item = String>) -> Warehouse {
     let mut empty_line_found = false;
-    let lines = read_input(2024, 15).unwrap().map_while(Result::ok);
 
     let mut grid: Vec<Vec<Cell>> = vec![];
     let mut moves: Vec<Move> = vec![];
@@ -60,5 +72,10 @@ pub fn init_warehouse() -> Warehouse {
             moves,
         },
         debugger: WarehouseDebugger::default(),
+        history: vec![],
     }
 }
+
+pub fn init_warehouse() -> Warehouse {
+    parse_warehouse(read_input(2024, 15).unwrap().map_while(Result::ok))
+}
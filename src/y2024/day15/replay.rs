@@ -0,0 +1,135 @@
+use std::io::{BufRead, Write};
+
+use super::{debugger::Frame, warehouse::Position};
+
+const HELP: &str = "Commands:
+  n | <enter>   next move
+  p             previous move
+  g <N>         jump to move N
+  f <ROW> <COL> first move after the current one pushing the box at ROW COL
+  c             toggle showing only moves that pushed boxes
+  h             show this help
+  q             quit";
+
+pub struct ReplayViewer<'a> {
+    frames: &'a [Frame],
+    current: usize,
+    only_box_changes: bool,
+}
+
+impl<'a> ReplayViewer<'a> {
+    pub fn new(frames: &'a [Frame]) -> Self {
+        ReplayViewer {
+            frames,
+            current: 0,
+            only_box_changes: false,
+        }
+    }
+
+    fn is_visible(&self, index: usize) -> bool {
+        index == 0 || !self.only_box_changes || !self.frames[index].pushed_boxes.is_empty()
+    }
+
+    fn step_forward(&mut self) {
+        if let Some(index) = (self.current + 1..self.frames.len()).find(|&i| self.is_visible(i)) {
+            self.current = index;
+        }
+    }
+
+    fn step_back(&mut self) {
+        if let Some(index) = (0..self.current).rev().find(|&i| self.is_visible(i)) {
+            self.current = index;
+        }
+    }
+
+    fn find_push(&self, position: &Position) -> Option<usize> {
+        (self.current + 1..self.frames.len()).find(|&index| {
+            self.frames[index]
+                .pushed_boxes
+                .iter()
+                .any(|cells| cells.contains(position))
+        })
+    }
+
+    fn render(&self, output: &mut impl Write) {
+        let frame = &self.frames[self.current];
+        let title = match &frame.r#move {
+            Some(r#move) => format!(
+                "Move {}/{} {:?}",
+                self.current,
+                self.frames.len() - 1,
+                r#move
+            ),
+            None => format!("Initial state (0/{})", self.frames.len() - 1),
+        };
+        let filter = if self.only_box_changes {
+            " [box changes only]"
+        } else {
+            ""
+        };
+
+        writeln!(output, "{}{}", title, filter).expect("Failed to write title");
+        write!(output, "{}", frame.state).expect("Failed to write grid");
+        if !frame.pushed_boxes.is_empty() {
+            writeln!(output, "Pushed boxes: {}", frame.pushed_boxes.len())
+                .expect("Failed to write pushed boxes");
+        }
+    }
+
+    pub fn run(&mut self, input: impl BufRead, output: &mut impl Write) {
+        if self.frames.is_empty() {
+            writeln!(output, "Nothing recorded").expect("Failed to write output");
+            return;
+        }
+
+        writeln!(output, "{}", HELP).expect("Failed to write help");
+        self.render(output);
+
+        for line in input.lines().map_while(Result::ok) {
+            let args = line.split_whitespace().collect::<Vec<&str>>();
+            match args.as_slice() {
+                [] | ["n"] => self.step_forward(),
+                ["p"] => self.step_back(),
+                ["g", n] => match n.parse::<usize>() {
+                    Ok(n) if n < self.frames.len() => self.current = n,
+                    _ => {
+                        writeln!(
+                            output,
+                            "Move must be between 0 and {}",
+                            self.frames.len() - 1
+                        )
+                        .expect("Failed to write output");
+                        continue;
+                    }
+                },
+                ["f", row, col] => match (row.parse::<usize>(), col.parse::<usize>()) {
+                    (Ok(row), Ok(col)) => match self.find_push(&Position(row, col)) {
+                        Some(index) => self.current = index,
+                        None => {
+                            writeln!(output, "No later move pushes a box at {} {}", row, col)
+                                .expect("Failed to write output");
+                            continue;
+                        }
+                    },
+                    _ => {
+                        writeln!(output, "Usage: f <ROW> <COL>").expect("Failed to write output");
+                        continue;
+                    }
+                },
+                ["c"] => self.only_box_changes = !self.only_box_changes,
+                ["h"] => {
+                    writeln!(output, "{}", HELP).expect("Failed to write help");
+                    continue;
+                }
+                ["q"] => return,
+                _ => {
+                    writeln!(output, "Unknown command, h for help")
+                        .expect("Failed to write output");
+                    continue;
+                }
+            }
+
+            self.render(output);
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::{
+    parser::format_moves,
+    warehouse::{Cell, Move, Position, Warehouse},
+};
+
+const MOVES: [Move; 4] = [Move::UP, Move::DOWN, Move::LEFT, Move::RIGHT];
+
+// Boxes are identified by their leftmost cell, kept sorted so equal states compare equal
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct State {
+    robot: Position,
+    boxes: Vec<Position>,
+}
+
+struct Node {
+    state: State,
+    parent: usize,
+    r#move: Option<Move>,
+}
+
+pub struct SokobanSolver<'a> {
+    warehouse: &'a mut Warehouse,
+    targets: Vec<Position>,
+    box_width: usize,
+    loaded_boxes: Vec<Position>,
+    // A stuck box is only a dead end when every box has to reach a target
+    prune_deadlocks: bool,
+}
+
+pub fn box_anchors(warehouse: &Warehouse) -> Vec<Position> {
+    let mut anchors = vec![];
+    for (row, cells) in warehouse.grid.iter().enumerate() {
+        for (col, cell) in cells.iter().enumerate() {
+            if *cell == Cell::Box || *cell == Cell::BoxLeft {
+                anchors.push(Position(row, col));
+            }
+        }
+    }
+    anchors
+}
+
+impl<'a> SokobanSolver<'a> {
+    pub fn new(warehouse: &'a mut Warehouse, targets: &[Position]) -> Self {
+        let loaded_boxes = box_anchors(warehouse);
+        let box_width = loaded_boxes
+            .first()
+            .map_or(1, |anchor| warehouse.box_cells(anchor).len());
+
+        SokobanSolver {
+            warehouse,
+            targets: targets.to_vec(),
+            box_width,
+            prune_deadlocks: loaded_boxes.len() <= targets.len(),
+            loaded_boxes,
+        }
+    }
+
+    fn is_solved(&self, state: &State) -> bool {
+        self.targets
+            .iter()
+            .all(|target| state.boxes.binary_search(target).is_ok())
+    }
+
+    fn is_wall(&self, position: &Position) -> bool {
+        self.warehouse.grid[position.0][position.1] == Cell::Wall
+    }
+
+    // A box off target that walls alone prevent from being pushed in any direction
+    // will never move again, so the state can't lead to a solution when there are
+    // no spare boxes.
+    fn is_deadlocked(&self, anchor: &Position) -> bool {
+        if self.targets.contains(anchor) {
+            return false;
+        }
+
+        let cells = (0..self.box_width)
+            .map(|offset| Position(anchor.0, anchor.1 + offset))
+            .collect::<Vec<_>>();
+        // Neighbours of the box in a direction, leaving out its own cells
+        let neighbours = |r#move: &Move| {
+            cells
+                .iter()
+                .map(|cell| Position::apply_move(cell, r#move))
+                .filter(|neighbour| !cells.contains(neighbour))
+                .collect::<Vec<_>>()
+        };
+        let can_push = |r#move: &Move, opposite: &Move| {
+            neighbours(r#move).iter().all(|cell| !self.is_wall(cell))
+                && neighbours(opposite).iter().any(|cell| !self.is_wall(cell))
+        };
+
+        !can_push(&Move::UP, &Move::DOWN)
+            && !can_push(&Move::DOWN, &Move::UP)
+            && !can_push(&Move::LEFT, &Move::RIGHT)
+            && !can_push(&Move::RIGHT, &Move::LEFT)
+    }
+
+    fn load(&mut self, state: &State) {
+        for anchor in std::mem::take(&mut self.loaded_boxes) {
+            for offset in 0..self.box_width {
+                self.warehouse.grid[anchor.0][anchor.1 + offset] = Cell::Empty;
+            }
+        }
+        for anchor in state.boxes.iter() {
+            let row = &mut self.warehouse.grid[anchor.0];
+            if self.box_width == 1 {
+                row[anchor.1] = Cell::Box;
+            } else {
+                row[anchor.1] = Cell::BoxLeft;
+                for offset in 1..self.box_width - 1 {
+                    row[anchor.1 + offset] = Cell::BoxMiddle;
+                }
+                row[anchor.1 + self.box_width - 1] = Cell::BoxRight;
+            }
+        }
+        self.loaded_boxes = state.boxes.clone();
+        self.warehouse.robot.position = state.robot.clone();
+    }
+
+    // Applies the move to the loaded state and undoes it, returning the resulting state
+    // unless the robot was blocked or a pushed box ends up deadlocked.
+    fn next_state(&mut self, state: &State, r#move: &Move) -> Option<State> {
+        self.warehouse.apply_robot_move(r#move);
+        let robot = self.warehouse.robot.position.clone();
+        let pushed_anchors = self
+            .warehouse
+            .history
+            .last()
+            .unwrap()
+            .pushed_cells
+            .iter()
+            .map(|(cell, _)| cell.clone())
+            .filter(|cell| state.boxes.binary_search(cell).is_ok())
+            .collect::<Vec<_>>();
+        self.warehouse.undo_move();
+
+        if robot == state.robot {
+            return None;
+        }
+
+        let mut boxes = state.boxes.clone();
+        for anchor in boxes.iter_mut() {
+            if pushed_anchors.contains(anchor) {
+                *anchor = Position::apply_move(anchor, r#move);
+                if self.prune_deadlocks && self.is_deadlocked(anchor) {
+                    return None;
+                }
+            }
+        }
+        boxes.sort();
+
+        Some(State { robot, boxes })
+    }
+
+    // The moves are returned in the `<>^v` format of the puzzle input.
+    // The warehouse is left as it was before solving.
+    pub fn solve(&mut self) -> Option<String> {
+        let mut boxes = self.loaded_boxes.clone();
+        boxes.sort();
+        let initial = State {
+            robot: self.warehouse.robot.position.clone(),
+            boxes,
+        };
+
+        let moves = self.search(&initial);
+        self.load(&initial);
+        moves.map(|moves| format_moves(&moves))
+    }
+
+    // Breadth-first search over robot moves, so the first solution found is a shortest one.
+    fn search(&mut self, initial: &State) -> Option<Vec<Move>> {
+        let mut nodes = vec![Node {
+            state: initial.clone(),
+            parent: 0,
+            r#move: None,
+        }];
+        let mut visited = HashSet::from([initial.clone()]);
+        let mut queue = VecDeque::from([0]);
+
+        while let Some(index) = queue.pop_front() {
+            let state = nodes[index].state.clone();
+            if self.is_solved(&state) {
+                let mut moves = vec![];
+                let mut current = index;
+                while let Some(r#move) = &nodes[current].r#move {
+                    moves.push(r#move.clone());
+                    current = nodes[current].parent;
+                }
+                moves.reverse();
+                return Some(moves);
+            }
+
+            self.load(&state);
+            for r#move in MOVES.iter() {
+                if let Some(next) = self.next_state(&state, r#move) {
+                    if visited.insert(next.clone()) {
+                        nodes.push(Node {
+                            state: next,
+                            parent: index,
+                            r#move: Some(r#move.clone()),
+                        });
+                        queue.push_back(nodes.len() - 1);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
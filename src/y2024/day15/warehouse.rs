@@ -1,11 +1,11 @@
 use std::fmt::{self, Debug};
 
-use super::debugger::WarehouseDebugger;
+use super::{debugger::WarehouseDebugger, invariants::InvariantViolation};
 
 #[derive(Clone, PartialEq, Eq)]
 pub struct Move(pub isize, pub isize);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Position(pub usize, pub usize);
 
 impl Position {
@@ -29,8 +29,9 @@ pub enum Cell {
     Box,
     Empty,
 
-    // For Part 2:
+    // For Part 2 and wider boxes:
     BoxLeft,
+    BoxMiddle,
     BoxRight,
 }
 
@@ -41,10 +42,19 @@ impl Move {
     pub const DOWN: Move = Move(1, 0);
 }
 
+// What a move changed, so it can be undone
+pub struct MoveRecord {
+    pub r#move: Move,
+    pub robot_position: Position,
+    // Cells of the pushed boxes before the move, with what they contained
+    pub pushed_cells: Vec<(Position, Cell)>,
+}
+
 pub struct Warehouse {
     pub robot: Robot,
     pub grid: Vec<Vec<Cell>>,
     pub debugger: WarehouseDebugger,
+    pub history: Vec<MoveRecord>,
 }
 
 impl fmt::Display for Warehouse {
@@ -60,6 +70,7 @@ impl fmt::Display for Warehouse {
                         Cell::Box => "O",
                         Cell::BoxRight => "]",
                         Cell::BoxLeft => "[",
+                        Cell::BoxMiddle => "=",
                     })
                     .collect::<String>()
             })
@@ -74,22 +85,28 @@ impl fmt::Display for Warehouse {
 }
 
 impl Warehouse {
-    pub fn make_wide(&mut self) {
+    pub fn make_wide(&mut self, width: usize) {
         self.grid = self
             .grid
             .iter()
             .map(|row| {
                 row.iter()
                     .flat_map(|cell| match cell {
-                        Cell::Wall => [Cell::Wall, Cell::Wall],
-                        Cell::Empty => [Cell::Empty, Cell::Empty],
-                        Cell::Box => [Cell::BoxLeft, Cell::BoxRight],
+                        Cell::Wall => vec![Cell::Wall; width],
+                        Cell::Empty => vec![Cell::Empty; width],
+                        Cell::Box if width == 1 => vec![Cell::Box],
+                        Cell::Box => {
+                            let mut wide_box = vec![Cell::BoxMiddle; width];
+                            wide_box[0] = Cell::BoxLeft;
+                            wide_box[width - 1] = Cell::BoxRight;
+                            wide_box
+                        }
                         v => panic!("Unsupported CellKind making wharehouse wide {:?}", v),
                     })
                     .collect()
             })
             .collect();
-        self.robot.position.1 *= 2;
+        self.robot.position.1 *= width;
     }
 
     pub fn compute_gps_coordinates_sum(&self) -> usize {
@@ -112,170 +129,139 @@ impl Warehouse {
             .sum()
     }
 
-    fn try_move_wide_box(&mut self, box_pos: &Position, r#move: &Move, is_readonly: bool) -> bool {
-        // When moving horizontally, it's similar as part 1
-        if *r#move == Move::LEFT || *r#move == Move::RIGHT {
-            let target_pos = Position::apply_move(&Position::apply_move(box_pos, r#move), r#move);
-
-            let target_cell = self.grid[target_pos.0][target_pos.1].clone();
-            return match target_cell {
-                // Can't move if it's a wall
-                Cell::Wall => false,
-                // Can move if it's empty
-                Cell::Empty => {
-                    if *r#move == Move::LEFT {
-                        self.grid[target_pos.0][target_pos.1] = Cell::BoxLeft;
-                        let right_pos: Position = Position::apply_move(box_pos, r#move);
-                        self.grid[right_pos.0][right_pos.1] = Cell::BoxRight;
-                    } else {
-                        self.grid[target_pos.0][target_pos.1] = Cell::BoxRight;
-                        let left_pos: Position = Position::apply_move(box_pos, r#move);
-                        self.grid[left_pos.0][left_pos.1] = Cell::BoxLeft;
-                    }
-                    self.grid[box_pos.0][box_pos.1] = Cell::Empty;
-                    return true;
-                }
-                // If there is a box, need to move it first so cell becomes empty
-                Cell::BoxLeft | Cell::BoxRight => {
-                    if self.try_move_wide_box(&target_pos, r#move, is_readonly) {
-                        if *r#move == Move::LEFT {
-                            self.grid[target_pos.0][target_pos.1] = Cell::BoxLeft;
-                            let right_pos: Position = Position::apply_move(box_pos, r#move);
-                            self.grid[right_pos.0][right_pos.1] = Cell::BoxRight;
-                        } else {
-                            self.grid[target_pos.0][target_pos.1] = Cell::BoxRight;
-                            let left_pos: Position = Position::apply_move(box_pos, r#move);
-                            self.grid[left_pos.0][left_pos.1] = Cell::BoxLeft;
-                        }
-                        self.grid[box_pos.0][box_pos.1] = Cell::Empty;
-                        return true;
-                    }
-                    false
-                }
-                v => panic!("Unsupported CellKind for part wide box {:?}", v),
-            };
-        }
+    pub fn has_same_state(&self, other: &Warehouse) -> bool {
+        self.robot.position == other.robot.position && self.grid == other.grid
+    }
 
-        // When moving vertically, need to handle both sides of the box at the same time
-        let (left_pos, right_pos) = if self.grid[box_pos.0][box_pos.1] == Cell::BoxLeft {
-            (box_pos.clone(), Position::apply_move(box_pos, &Move::RIGHT))
-        } else {
-            (Position::apply_move(box_pos, &Move::LEFT), box_pos.clone())
-        };
-        let (left_target_pos, right_target_pos) = (
-            Position::apply_move(&left_pos, r#move),
-            Position::apply_move(&right_pos, r#move),
-        );
-
-        let (left_target, right_target) = (
-            &self.grid[left_target_pos.0][left_target_pos.1].clone(),
-            &self.grid[right_target_pos.0][right_target_pos.1].clone(),
-        );
-
-        // If at least one side face a wall, can't move
-        if *left_target == Cell::Wall || *right_target == Cell::Wall {
-            return false;
-        }
+    pub fn is_box(&self, position: &Position) -> bool {
+        matches!(
+            self.grid[position.0][position.1],
+            Cell::Box | Cell::BoxLeft | Cell::BoxMiddle | Cell::BoxRight
+        )
+    }
 
-        // If both target cells are empty, no blocker, can move there
-        if *left_target == Cell::Empty && *right_target == Cell::Empty {
-            if !is_readonly {
-                self.move_box(&left_pos, &right_pos, &left_target_pos, &right_target_pos);
-            }
-            return true;
+    // All the cells of the box covering `position`. A box only spans a row for now,
+    // but the push below works for any shape returned here.
+    pub fn box_cells(&self, position: &Position) -> Vec<Position> {
+        let row = &self.grid[position.0];
+        let mut left = position.1;
+        while row[left] != Cell::Box && row[left] != Cell::BoxLeft {
+            left -= 1;
+        }
+        let mut right = left;
+        while row[right] != Cell::Box && row[right] != Cell::BoxRight {
+            right += 1;
         }
 
-        // 2 boxes to move
-        // [][]
-        //  []
-        // Try readonly first to see if both can move, then do then move.
-        // Else it could move one but not the other because it's blocked.
-        if *left_target == Cell::BoxRight && *right_target == Cell::BoxLeft {
-            if self.try_move_wide_box(&left_target_pos, r#move, true)
-                && self.try_move_wide_box(&right_target_pos, r#move, true)
-            {
-                if !is_readonly {
-                    self.try_move_wide_box(&left_target_pos, r#move, is_readonly);
-                    self.try_move_wide_box(&right_target_pos, r#move, is_readonly);
-                    self.move_box(&left_pos, &right_pos, &left_target_pos, &right_target_pos);
-                }
+        (left..=right)
+            .map(|col| Position(position.0, col))
+            .collect()
+    }
 
-                return true;
+    // Collects every box that moves when pushing the box at `box_pos`, following the
+    // boxes each one runs into. Returns None if any of them would hit a wall.
+    fn collect_pushed_boxes(
+        &self,
+        box_pos: &Position,
+        r#move: &Move,
+    ) -> Option<Vec<Vec<Position>>> {
+        let mut boxes = vec![self.box_cells(box_pos)];
+        let mut next = 0;
+
+        while next < boxes.len() {
+            for cell in boxes[next].clone() {
+                let target_pos = Position::apply_move(&cell, r#move);
+                if self.grid[target_pos.0][target_pos.1] == Cell::Wall {
+                    return None;
+                }
+                if self.is_box(&target_pos)
+                    && !boxes.iter().any(|cells| cells.contains(&target_pos))
+                {
+                    boxes.push(self.box_cells(&target_pos));
+                }
             }
-            return false;
+            next += 1;
         }
 
-        //   []
-        //  []
-        if *left_target == Cell::Empty
-            && self.try_move_wide_box(&right_target_pos, r#move, is_readonly)
-        {
-            if !is_readonly {
-                self.move_box(&left_pos, &right_pos, &left_target_pos, &right_target_pos);
-            }
-            return true;
-        }
+        Some(boxes)
+    }
 
-        // []
-        //  []
-        if *right_target == Cell::Empty
-            && self.try_move_wide_box(&left_target_pos, r#move, is_readonly)
-        {
-            if !is_readonly {
-                self.move_box(&left_pos, &right_pos, &left_target_pos, &right_target_pos);
-            }
-            return true;
+    // Moves all the affected boxes at once, or none of them if they're blocked.
+    // Returns the cells the boxes were moved from.
+    fn try_push_boxes(
+        &mut self,
+        box_pos: &Position,
+        r#move: &Move,
+    ) -> Option<Vec<(Position, Cell)>> {
+        let boxes = self.collect_pushed_boxes(box_pos, r#move)?;
+
+        let moved_cells = boxes
+            .iter()
+            .flatten()
+            .map(|cell| (cell.clone(), self.grid[cell.0][cell.1].clone()))
+            .collect::<Vec<_>>();
+        for (cell, _) in moved_cells.iter() {
+            self.grid[cell.0][cell.1] = Cell::Empty;
+        }
+        for (cell, kind) in moved_cells.iter() {
+            let target_pos = Position::apply_move(cell, r#move);
+            self.grid[target_pos.0][target_pos.1] = kind.clone();
         }
 
-        //  []
-        //  []
-        if *right_target == Cell::BoxRight
-            && *left_target == Cell::BoxLeft
-            && self.try_move_wide_box(&left_target_pos, r#move, is_readonly)
-        {
-            if !is_readonly {
-                self.move_box(&left_pos, &right_pos, &left_target_pos, &right_target_pos);
+        if self.debugger.is_activated {
+            for cells in boxes {
+                self.debugger.record_pushed_box(cells);
             }
-            return true;
         }
 
-        false
+        Some(moved_cells)
     }
 
-    fn move_box(
-        &mut self,
-        left_pos: &Position,
-        right_pos: &Position,
-        left_target_pos: &Position,
-        right_target_pos: &Position,
-    ) {
-        self.grid[left_target_pos.0][left_target_pos.1] = Cell::BoxLeft;
-        self.grid[right_target_pos.0][right_target_pos.1] = Cell::BoxRight;
-        self.grid[left_pos.0][left_pos.1] = Cell::Empty;
-        self.grid[right_pos.0][right_pos.1] = Cell::Empty;
-    }
+    pub fn apply_robot_move(&mut self, r#move: &Move) {
+        let robot_position = self.robot.position.clone();
+        let mut pushed_cells = vec![];
 
-    fn try_move_box(&mut self, box_pos: &Position, r#move: &Move) -> bool {
-        let target_pos = Position::apply_move(box_pos, r#move);
+        let target_cell_position = Position::apply_move(&self.robot.position, r#move);
+        let target_cell = &self.grid[target_cell_position.0][target_cell_position.1];
 
-        match &self.grid[target_pos.0][target_pos.1] {
-            Cell::Wall => false,
+        match &target_cell {
+            Cell::Wall => {}
             Cell::Empty => {
-                self.grid[target_pos.0][target_pos.1] = Cell::Box;
-                self.grid[box_pos.0][box_pos.1] = Cell::Empty;
-                true
+                self.robot.position = target_cell_position;
             }
-            Cell::Box => {
-                if self.try_move_box(&target_pos, r#move) {
-                    self.grid[target_pos.0][target_pos.1] = Cell::Box;
-                    self.grid[box_pos.0][box_pos.1] = Cell::Empty;
-                    true
-                } else {
-                    false
+            _ => {
+                if let Some(moved_cells) = self.try_push_boxes(&target_cell_position, r#move) {
+                    pushed_cells = moved_cells;
+                    self.robot.position = target_cell_position;
                 }
             }
-            v => panic!("Unsupported CellKind for part 1 {:?}", v),
         }
+
+        self.history.push(MoveRecord {
+            r#move: r#move.clone(),
+            robot_position,
+            pushed_cells,
+        });
+
+        if self.debugger.is_activated {
+            self.debugger.debug_move(r#move, &self.to_string());
+        }
+    }
+
+    // Rewinds the last applied move and returns it
+    pub fn undo_move(&mut self) -> Option<Move> {
+        let record = self.history.pop()?;
+
+        for (cell, _) in record.pushed_cells.iter() {
+            let target_pos = Position::apply_move(cell, &record.r#move);
+            self.grid[target_pos.0][target_pos.1] = Cell::Empty;
+        }
+        for (cell, kind) in record.pushed_cells {
+            self.grid[cell.0][cell.1] = kind;
+        }
+        self.robot.position = record.robot_position;
+
+        Some(record.r#move)
     }
 
     pub fn apply_robot_moves(&mut self) {
@@ -284,29 +270,27 @@ impl Warehouse {
         }
 
         for r#move in self.robot.moves.clone().into_iter() {
-            let target_cell_position = Position::apply_move(&self.robot.position, &r#move);
-            let target_cell = &self.grid[target_cell_position.0][target_cell_position.1];
+            self.apply_robot_move(&r#move);
+        }
+    }
 
-            match &target_cell {
-                Cell::Wall => continue,
-                Cell::Empty => {
-                    self.robot.position = target_cell_position;
-                }
-                Cell::Box => {
-                    if self.try_move_box(&target_cell_position, &r#move) {
-                        self.robot.position = target_cell_position;
-                    }
-                }
-                _ => {
-                    if self.try_move_wide_box(&target_cell_position, &r#move, false) {
-                        self.robot.position = target_cell_position;
-                    }
-                }
-            }
+    // Same as apply_robot_moves, but stops at the first move breaking an invariant.
+    // Move 0 is the initial state.
+    pub fn apply_robot_moves_checked(&mut self) -> Result<(), (usize, InvariantViolation)> {
+        let box_count = self.count_boxes();
+        self.check_invariants(box_count)
+            .map_err(|violation| (0, violation))?;
 
-            if self.debugger.is_activated {
-                self.debugger.debug_move(&r#move, &self.to_string());
-            }
+        if self.debugger.is_activated {
+            self.debugger.debug_initial_state(&self.to_string());
         }
+
+        for (index, r#move) in self.robot.moves.clone().into_iter().enumerate() {
+            self.apply_robot_move(&r#move);
+            self.check_invariants(box_count)
+                .map_err(|violation| (index + 1, violation))?;
+        }
+
+        Ok(())
     }
 }
@@ -1,12 +1,15 @@
-use lazy_static::lazy_static;
+mod render;
+mod routes;
+
 use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fs;
 
-use crate::read_input;
+use render::{extract_tile_scores, MazeRenderer, RenderFormat};
+use routes::{find_k_shortest_routes, find_tiles_within_slack};
 
-const ROTATION_COST: usize = 1000;
-const FORWARD_COST: usize = 1;
+use crate::{option_value, read_input};
 
-type Maze = Vec<Vec<char>>;
+const RENDER_FILE: &str = "src/y2024/day16/best_paths.ppm";
 
 // Represents a candidate position with its direction and score for Dijkstra
 #[derive(Eq, PartialEq, Debug)]
@@ -44,24 +47,18 @@ const WEST: Direction = Direction { row: 0, col: -1 };
 const SOUTH: Direction = Direction { row: 1, col: 0 };
 const NORTH: Direction = Direction { row: -1, col: 0 };
 
-lazy_static! {
-    static ref ROTATE_CLOCKWISE: HashMap<Direction, Direction> = {
-        let mut m = HashMap::new();
-        m.insert(NORTH, EAST);
-        m.insert(EAST, SOUTH);
-        m.insert(SOUTH, WEST);
-        m.insert(WEST, NORTH);
-        m
-    };
-    static ref ROTATE_COUNTERCLOCKWISE: HashMap<Direction, Direction> = {
-        let mut m = HashMap::new();
-        m.insert(EAST, NORTH);
-        m.insert(SOUTH, EAST);
-        m.insert(WEST, SOUTH);
-        m.insert(NORTH, WEST);
-        m
-    };
-}
+// Clockwise, so rotating is moving to the next or previous direction
+const DIRECTIONS: [Direction; 4] = [NORTH, EAST, SOUTH, WEST];
+const DIRECTIONS_WITH_DIAGONALS: [Direction; 8] = [
+    NORTH,
+    Direction { row: -1, col: 1 },
+    EAST,
+    Direction { row: 1, col: 1 },
+    SOUTH,
+    Direction { row: 1, col: -1 },
+    WEST,
+    Direction { row: -1, col: -1 },
+];
 
 #[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
 struct Position {
@@ -76,36 +73,204 @@ impl Position {
             col: (self.col as isize + direction.col) as usize,
         }
     }
+
+    fn apply_opposite_direction(&self, direction: &Direction) -> Self {
+        Position {
+            row: (self.row as isize - direction.row) as usize,
+            col: (self.col as isize - direction.col) as usize,
+        }
+    }
+}
+
+struct Maze {
+    grid: Vec<Vec<char>>,
+    start: Position,
+    end: Position,
+}
+
+impl Maze {
+    fn is_wall(&self, position: &Position) -> bool {
+        self.grid[position.row][position.col] == '#'
+    }
+}
+
+// How the reindeer can move. Defaults are the puzzle rules.
+struct MazeRules {
+    forward_cost: usize,
+    // Cost of a 90° turn
+    rotation_cost: usize,
+    // Cost of a 45° turn when moving diagonally is allowed, half a 90° turn by default
+    diagonal_rotation_cost: Option<usize>,
+    // Cost of turning around in a single step, if allowed
+    u_turn_cost: Option<usize>,
+    allow_diagonals: bool,
+    start_direction: Direction,
+}
+
+impl Default for MazeRules {
+    fn default() -> Self {
+        MazeRules {
+            forward_cost: 1,
+            rotation_cost: 1000,
+            diagonal_rotation_cost: None,
+            u_turn_cost: None,
+            allow_diagonals: false,
+            start_direction: EAST,
+        }
+    }
+}
+
+impl MazeRules {
+    // --forward-cost N, --rotation-cost N, --diagonal-rotation-cost N, --u-turn-cost N
+    // and --diagonals
+    fn from_options(options: &[String]) -> Self {
+        let default = MazeRules::default();
+        let cost = |name: &str| {
+            option_value(options, name).map(|cost| {
+                cost.parse::<usize>()
+                    .unwrap_or_else(|_| panic!("{} expects a number", name))
+            })
+        };
+
+        MazeRules {
+            forward_cost: cost("--forward-cost").unwrap_or(default.forward_cost),
+            rotation_cost: cost("--rotation-cost").unwrap_or(default.rotation_cost),
+            diagonal_rotation_cost: cost("--diagonal-rotation-cost"),
+            u_turn_cost: cost("--u-turn-cost"),
+            allow_diagonals: options.iter().any(|option| option == "--diagonals"),
+            ..default
+        }
+    }
+
+    fn directions(&self) -> &'static [Direction] {
+        if self.allow_diagonals {
+            &DIRECTIONS_WITH_DIAGONALS
+        } else {
+            &DIRECTIONS
+        }
+    }
+
+    // Directions reachable by turning on the spot, with the cost of the turn
+    // Turns are symmetric, so these are also the directions the turn could come from
+    fn rotations(&self, direction: &Direction) -> Vec<(Direction, usize)> {
+        let directions = self.directions();
+        let index = directions.iter().position(|d| d == direction).unwrap();
+        // directions `steps` positions away in the clockwise order, either way
+        let turn = |steps: usize| {
+            [
+                directions[(index + steps) % directions.len()],
+                directions[(index + directions.len() - steps) % directions.len()],
+            ]
+        };
+        // with diagonals, a 90° turn is two steps away
+        let quarter_turn = directions.len() / 4;
+
+        let mut rotations = turn(quarter_turn)
+            .map(|rotated| (rotated, self.rotation_cost))
+            .to_vec();
+        if self.allow_diagonals {
+            let cost = self
+                .diagonal_rotation_cost
+                .unwrap_or(self.rotation_cost / 2);
+            rotations.extend(turn(1).map(|rotated| (rotated, cost)));
+        }
+        if let Some(u_turn_cost) = self.u_turn_cost {
+            rotations.push((turn(2 * quarter_turn)[0], u_turn_cost));
+        }
+        rotations
+    }
+
+    // A diagonal move can't squeeze between two walls touching by their corners
+    fn can_move(&self, maze: &Maze, from: &Position, direction: &Direction) -> bool {
+        let to = from.apply_direction(direction);
+        let is_squeezed = direction.row != 0
+            && direction.col != 0
+            && maze.is_wall(&Position {
+                row: to.row,
+                col: from.col,
+            })
+            && maze.is_wall(&Position {
+                row: from.row,
+                col: to.col,
+            });
+
+        !maze.is_wall(&to) && !is_squeezed
+    }
+
+    fn next_states(
+        &self,
+        maze: &Maze,
+        position: &Position,
+        direction: &Direction,
+    ) -> Vec<(Position, Direction, usize)> {
+        let mut states = self
+            .rotations(direction)
+            .into_iter()
+            .map(|(rotated, cost)| (*position, rotated, cost))
+            .collect::<Vec<_>>();
+
+        let next_position = position.apply_direction(direction);
+        if self.can_move(maze, position, direction) {
+            states.push((next_position, *direction, self.forward_cost));
+        }
+        states
+    }
+
+    fn previous_states(
+        &self,
+        maze: &Maze,
+        position: &Position,
+        direction: &Direction,
+    ) -> Vec<(Position, Direction, usize)> {
+        let mut states = self
+            .rotations(direction)
+            .into_iter()
+            .map(|(rotated, cost)| (*position, rotated, cost))
+            .collect::<Vec<_>>();
+
+        let previous_position = position.apply_opposite_direction(direction);
+        if !maze.is_wall(&previous_position) && self.can_move(maze, &previous_position, direction) {
+            states.push((previous_position, *direction, self.forward_cost));
+        }
+        states
+    }
 }
 
 type SmallestScoresByTile = HashMap<(Position, Direction), usize>;
 
 fn parse_maze() -> Maze {
-    read_input(2024, 16)
+    let grid = read_input(2024, 16)
         .unwrap()
         .map_while(Result::ok)
         .map(|line| line.chars().collect::<Vec<_>>())
-        .collect::<Vec<_>>()
+        .collect::<Vec<_>>();
+    let (start, end) = extract_start_end(&grid);
+
+    Maze { grid, start, end }
 }
 
-fn extract_start_end(maze: &Maze) -> (Position, Position) {
-    let start = Position {
-        row: maze.len() - 2,
-        col: 1,
-    };
-    let end = Position {
-        row: 1,
-        col: maze[0].len() - 2,
-    };
+fn find_marker(grid: &[Vec<char>], marker: char) -> Position {
+    grid.iter()
+        .enumerate()
+        .find_map(|(row, line)| {
+            line.iter()
+                .position(|c| *c == marker)
+                .map(|col| Position { row, col })
+        })
+        .unwrap_or_else(|| panic!("No {} marker in the maze", marker))
+}
 
-    (start, end)
+fn extract_start_end(grid: &[Vec<char>]) -> (Position, Position) {
+    (find_marker(grid, 'S'), find_marker(grid, 'E'))
 }
 
 fn extract_smallest_score_for_position(
     smallest_scores: &SmallestScoresByTile,
+    rules: &MazeRules,
     position: &Position,
 ) -> (Direction, usize) {
-    let (dir, score) = [EAST, NORTH, SOUTH, WEST]
+    let (dir, score) = rules
+        .directions()
         .iter()
         .filter_map(|dir| {
             smallest_scores
@@ -119,10 +284,10 @@ fn extract_smallest_score_for_position(
 }
 
 // returns the smallest score for each tile of the maze
-fn dijkstra(maze: Maze, start: Position) -> SmallestScoresByTile {
+fn dijkstra(maze: &Maze, rules: &MazeRules) -> SmallestScoresByTile {
     let start_tile = TileCandidateScore {
-        position: start,
-        direction: EAST,
+        position: maze.start,
+        direction: rules.start_direction,
         score: 0,
     };
 
@@ -140,115 +305,135 @@ fn dijkstra(maze: Maze, start: Position) -> SmallestScoresByTile {
         // this is the best score to reach this position with this direction (because min BinaryHeap)
         smallest_scores.insert((candidate.position, candidate.direction), candidate.score);
 
-        let next_position = candidate.position.apply_direction(&candidate.direction);
-
-        if maze[next_position.row][next_position.col] != '#' {
+        for (position, direction, cost) in
+            rules.next_states(maze, &candidate.position, &candidate.direction)
+        {
             queue.push(TileCandidateScore {
-                position: next_position,
-                direction: candidate.direction,
-                score: candidate.score + FORWARD_COST,
+                position,
+                direction,
+                score: candidate.score + cost,
             });
         }
-
-        queue.push(TileCandidateScore {
-            position: candidate.position,
-            direction: *ROTATE_CLOCKWISE.get(&candidate.direction).unwrap(),
-            score: candidate.score + ROTATION_COST,
-        });
-        queue.push(TileCandidateScore {
-            position: candidate.position,
-            direction: *ROTATE_COUNTERCLOCKWISE.get(&candidate.direction).unwrap(),
-            score: candidate.score + ROTATION_COST,
-        });
     }
 
     smallest_scores
 }
 
-fn calculate_rotations(from_direction: Direction, to_direction: Direction) -> usize {
-    [&*ROTATE_CLOCKWISE, &*ROTATE_COUNTERCLOCKWISE]
-        .iter()
-        .map(|rotate_map| {
-            let mut rotations = 0;
-            let mut current = from_direction;
-            while current != to_direction {
-                current = rotate_map[&current];
-                rotations += 1;
-            }
-            rotations
-        })
-        .min()
-        .unwrap()
-}
-
 // With dijkstra, we have the smallest score for each direction for each tile.
-// Start at the end position, and go through the tiles backward following the best paths
+// Start at the end position, and go through the states backward following the best paths
 // to discover all tiles beloging to a best path
-fn backward_tracing(smallest_scores: &SmallestScoresByTile, end: &Position) -> HashSet<Position> {
-    let (smallest_direction, smallest_score) =
-        extract_smallest_score_for_position(smallest_scores, end);
-
-    let mut tiles: HashSet<Position> = HashSet::new();
-    let mut queue = [(*end, (smallest_direction, smallest_score))].to_vec();
-
-    while !queue.is_empty() {
-        let mut next_queue = Vec::new();
-
-        for (tile, (smallest_direction, smallest_score)) in queue {
-            tiles.insert(tile);
-
-            // the idea is to find where we could have come from to get the score of `tile`
-
-            // The smallest score of `tile` has been obtained using one of the 4 directions
-            // -> find the predecessor tile based on this direction
-            let opposite_dir = ROTATE_CLOCKWISE[&ROTATE_CLOCKWISE[&smallest_direction]];
-            let predecessor_pos = tile.apply_direction(&opposite_dir);
-
-            let predecessor_entries = [
-                (smallest_scores.get(&(predecessor_pos, NORTH)), NORTH),
-                (smallest_scores.get(&(predecessor_pos, SOUTH)), SOUTH),
-                (smallest_scores.get(&(predecessor_pos, EAST)), EAST),
-                (smallest_scores.get(&(predecessor_pos, WEST)), WEST),
-            ]
-            .to_vec();
-
-            // Check the entries of this predecessor tile
-            // to find which ones are valid entries for smallest score paths
-            for (entry_score, entry_direction) in predecessor_entries {
-                if let Some(score) = entry_score {
-                    // find the number of rotations needed for the entry to be in the same direction as `tile`
-                    let rotations = calculate_rotations(entry_direction, smallest_direction);
-
-                    if score + rotations * ROTATION_COST + FORWARD_COST == smallest_score {
-                        // the entry tile belongs to a best path
-                        next_queue.push((predecessor_pos, (entry_direction, *score)));
-                    }
-                }
+fn backward_tracing(
+    smallest_scores: &SmallestScoresByTile,
+    maze: &Maze,
+    rules: &MazeRules,
+) -> HashSet<Position> {
+    let (_, smallest_score) =
+        extract_smallest_score_for_position(smallest_scores, rules, &maze.end);
+
+    // The end can be reached facing several directions with the same score
+    let mut queue = rules
+        .directions()
+        .iter()
+        .filter(|dir| smallest_scores.get(&(maze.end, **dir)) == Some(&smallest_score))
+        .map(|dir| (maze.end, *dir))
+        .collect::<Vec<_>>();
+    let mut visited: HashSet<(Position, Direction)> = queue.iter().copied().collect();
+
+    while let Some((position, direction)) = queue.pop() {
+        let score = smallest_scores[&(position, direction)];
+
+        // the idea is to find where we could have come from to get the score of this state
+        for (previous_position, previous_direction, cost) in
+            rules.previous_states(maze, &position, &direction)
+        {
+            let previous_state = (previous_position, previous_direction);
+            if smallest_scores.get(&previous_state).map(|s| s + cost) == Some(score)
+                && visited.insert(previous_state)
+            {
+                // the previous state belongs to a best path
+                queue.push(previous_state);
             }
         }
-
-        queue = next_queue;
     }
 
-    tiles
+    visited.into_iter().map(|(position, _)| position).collect()
 }
 
-pub fn run_part_1() {
+pub fn run_part_1(options: &[String]) {
     let maze = parse_maze();
-    let (start, end) = extract_start_end(&maze);
+    let rules = MazeRules::from_options(options);
 
-    let smallest_scores = dijkstra(maze, start);
-    let (_, min_distance) = extract_smallest_score_for_position(&smallest_scores, &end);
+    let smallest_scores = dijkstra(&maze, &rules);
+    let (_, min_distance) =
+        extract_smallest_score_for_position(&smallest_scores, &rules, &maze.end);
 
     println!("{:?}", min_distance);
 }
 
-pub fn run_part_2() {
+pub fn run_part_2(options: &[String]) {
     let maze = parse_maze();
-    let (start, end) = extract_start_end(&maze);
+    let rules = MazeRules::from_options(options);
 
-    let smallest_scores = dijkstra(maze, start);
-    let best_paths_tiles = backward_tracing(&smallest_scores, &end);
+    let smallest_scores = dijkstra(&maze, &rules);
+    let best_paths_tiles = backward_tracing(&smallest_scores, &maze, &rules);
 
     println!("{:?}", best_paths_tiles.len());
+
+    render_best_paths(&maze, &smallest_scores, &best_paths_tiles, options);
+    report_near_optimal_paths(&maze, &rules, &smallest_scores, options);
+}
+
+// --routes K lists the K cheapest routes, --slack N counts the tiles on paths
+// scoring at most N more than the best one
+fn report_near_optimal_paths(
+    maze: &Maze,
+    rules: &MazeRules,
+    smallest_scores: &SmallestScoresByTile,
+    options: &[String],
+) {
+    if let Some(k) = option_value(options, "--routes")
+        .map(|k| k.parse::<usize>().expect("--routes expects a number"))
+    {
+        for (index, route) in find_k_shortest_routes(maze, rules, k).iter().enumerate() {
+            println!(
+                "Route {}: score {}, {} turns",
+                index + 1,
+                route.score,
+                route.turns()
+            );
+        }
+    }
+
+    if let Some(slack) = option_value(options, "--slack")
+        .map(|slack| slack.parse::<usize>().expect("--slack expects a number"))
+    {
+        let tiles = find_tiles_within_slack(maze, rules, smallest_scores, slack);
+        println!("Tiles within {} of the best score: {}", slack, tiles.len());
+    }
+}
+
+// --render ascii|ansi|ppm draws the best paths tiles, --scores colours them by score
+// The PPM image is written to RENDER_FILE, the other formats to stdout
+fn render_best_paths(
+    maze: &Maze,
+    smallest_scores: &SmallestScoresByTile,
+    best_paths_tiles: &HashSet<Position>,
+    options: &[String],
+) {
+    let Some(format) = option_value(options, "--render")
+        .map(|format| RenderFormat::parse(format).expect("--render expects ascii, ansi or ppm"))
+    else {
+        return;
+    };
+
+    let tile_scores = options
+        .iter()
+        .any(|option| option == "--scores")
+        .then(|| extract_tile_scores(smallest_scores));
+    let rendered = MazeRenderer::new(maze, best_paths_tiles, tile_scores).render(&format);
+
+    match format {
+        RenderFormat::Ppm => fs::write(RENDER_FILE, rendered).expect("Failed to write image"),
+        _ => print!("{}", rendered),
+    }
 }
@@ -0,0 +1,158 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{Maze, Position, SmallestScoresByTile};
+
+// Each tile is drawn as a square of this many pixels in the image
+const PPM_TILE_SIZE: usize = 4;
+
+type Color = (u8, u8, u8);
+
+const WALL_COLOR: Color = (40, 40, 40);
+const FLOOR_COLOR: Color = (230, 230, 230);
+const PATH_COLOR: Color = (255, 140, 0);
+const MARKER_COLOR: Color = (30, 90, 255);
+
+pub enum RenderFormat {
+    Ascii,
+    Ansi,
+    Ppm,
+}
+
+impl RenderFormat {
+    pub fn parse(format: &str) -> Option<RenderFormat> {
+        match format {
+            "ascii" => Some(RenderFormat::Ascii),
+            "ansi" => Some(RenderFormat::Ansi),
+            "ppm" => Some(RenderFormat::Ppm),
+            _ => None,
+        }
+    }
+}
+
+// Smallest score reaching each tile, whatever the direction
+pub fn extract_tile_scores(smallest_scores: &SmallestScoresByTile) -> HashMap<Position, usize> {
+    let mut tile_scores: HashMap<Position, usize> = HashMap::new();
+    for ((position, _), score) in smallest_scores {
+        let tile_score = tile_scores.entry(*position).or_insert(*score);
+        *tile_score = (*tile_score).min(*score);
+    }
+    tile_scores
+}
+
+pub struct MazeRenderer<'a> {
+    maze: &'a Maze,
+    best_paths_tiles: &'a HashSet<Position>,
+    // When set, best path tiles go from green to red as their score grows
+    tile_scores: Option<HashMap<Position, usize>>,
+    max_score: usize,
+}
+
+impl<'a> MazeRenderer<'a> {
+    pub fn new(
+        maze: &'a Maze,
+        best_paths_tiles: &'a HashSet<Position>,
+        tile_scores: Option<HashMap<Position, usize>>,
+    ) -> Self {
+        let max_score = tile_scores.as_ref().map_or(1, |tile_scores| {
+            best_paths_tiles
+                .iter()
+                .filter_map(|tile| tile_scores.get(tile))
+                .max()
+                .copied()
+                .unwrap_or(0)
+                .max(1)
+        });
+
+        MazeRenderer {
+            maze,
+            best_paths_tiles,
+            tile_scores,
+            max_score,
+        }
+    }
+
+    fn path_color(&self, position: &Position) -> Color {
+        let Some(tile_scores) = &self.tile_scores else {
+            return PATH_COLOR;
+        };
+
+        let score = tile_scores.get(position).copied().unwrap_or(0);
+        let red = (255 * score / self.max_score) as u8;
+
+        (red, 255 - red, 0)
+    }
+
+    fn tile(&self, position: &Position) -> (char, Color) {
+        let c = self.maze.grid[position.row][position.col];
+        match c {
+            '#' => (c, WALL_COLOR),
+            'S' | 'E' => (c, MARKER_COLOR),
+            _ if self.best_paths_tiles.contains(position) => ('O', self.path_color(position)),
+            _ => (c, FLOOR_COLOR),
+        }
+    }
+
+    fn positions(&self) -> impl Iterator<Item = Vec<Position>> + '_ {
+        self.maze
+            .grid
+            .iter()
+            .enumerate()
+            .map(|(row, line)| (0..line.len()).map(|col| Position { row, col }).collect())
+    }
+
+    fn render_ascii(&self) -> String {
+        self.positions()
+            .map(|row| {
+                row.iter()
+                    .map(|position| self.tile(position).0)
+                    .collect::<String>()
+                    + "\n"
+            })
+            .collect()
+    }
+
+    fn render_ansi(&self) -> String {
+        self.positions()
+            .map(|row| {
+                row.iter()
+                    .map(|position| {
+                        let (c, (r, g, b)) = self.tile(position);
+                        format!("\x1b[38;2;{};{};{}m{}", r, g, b, c)
+                    })
+                    .collect::<String>()
+                    + "\x1b[0m\n"
+            })
+            .collect()
+    }
+
+    // Plain text PPM (P3), readable by most image viewers
+    fn render_ppm(&self) -> String {
+        let height = self.maze.grid.len() * PPM_TILE_SIZE;
+        let width = self.maze.grid[0].len() * PPM_TILE_SIZE;
+        let mut image = format!("P3\n{} {}\n255\n", width, height);
+
+        for row in self.positions() {
+            let pixels = row
+                .iter()
+                .map(|position| {
+                    let (_, (r, g, b)) = self.tile(position);
+                    format!("{} {} {} ", r, g, b).repeat(PPM_TILE_SIZE)
+                })
+                .collect::<String>();
+            for _ in 0..PPM_TILE_SIZE {
+                image.push_str(pixels.trim_end());
+                image.push('\n');
+            }
+        }
+
+        image
+    }
+
+    pub fn render(&self, format: &RenderFormat) -> String {
+        match format {
+            RenderFormat::Ascii => self.render_ascii(),
+            RenderFormat::Ansi => self.render_ansi(),
+            RenderFormat::Ppm => self.render_ppm(),
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use super::{Direction, Maze, MazeRules, Position, SmallestScoresByTile, TileCandidateScore};
+
+type State = (Position, Direction);
+
+// A* candidate, ranked by its score plus its score to the end
+// On ties, the candidate closest to the end comes first
+#[derive(Eq, PartialEq)]
+struct RouteCandidate {
+    estimated_score: usize,
+    score: usize,
+    state: State,
+}
+
+impl Ord for RouteCandidate {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other
+            .estimated_score
+            .cmp(&self.estimated_score)
+            .then_with(|| self.score.cmp(&other.score))
+    }
+}
+impl PartialOrd for RouteCandidate {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// A route through the state graph, from the start to the first state reaching the end tile
+#[derive(Clone, PartialEq, Eq)]
+pub struct Route {
+    pub states: Vec<State>,
+    pub score: usize,
+}
+
+impl Route {
+    // Every step staying on the same tile is a turn
+    pub fn turns(&self) -> usize {
+        self.states
+            .windows(2)
+            .filter(|pair| pair[0].0 == pair[1].0)
+            .count()
+    }
+}
+
+fn transition_cost(maze: &Maze, rules: &MazeRules, from: &State, to: &State) -> usize {
+    rules
+        .next_states(maze, &from.0, &from.1)
+        .into_iter()
+        .find(|(position, direction, _)| (*position, *direction) == *to)
+        .map(|(_, _, cost)| cost)
+        .unwrap()
+}
+
+// A* from `source` to the end tile, never going through the removed states or transitions
+// The scores to the end on the full maze never overestimate, so they guide the search
+fn find_shortest_route(
+    maze: &Maze,
+    rules: &MazeRules,
+    scores_to_end: &SmallestScoresByTile,
+    source: State,
+    removed_states: &HashSet<State>,
+    removed_transitions: &HashSet<(State, State)>,
+) -> Option<Route> {
+    let mut queue = BinaryHeap::new();
+    queue.push(RouteCandidate {
+        estimated_score: *scores_to_end.get(&source)?,
+        score: 0,
+        state: source,
+    });
+
+    let mut smallest_scores: SmallestScoresByTile = HashMap::new();
+    // Best score found so far for states still in the queue, with the state it came from
+    let mut tentative_scores: HashMap<State, (usize, State)> = HashMap::new();
+    let mut parents: HashMap<State, State> = HashMap::new();
+
+    while let Some(candidate) = queue.pop() {
+        let state = candidate.state;
+        if smallest_scores.contains_key(&state) {
+            continue;
+        }
+        smallest_scores.insert(state, candidate.score);
+        if let Some((_, parent)) = tentative_scores.remove(&state) {
+            parents.insert(state, parent);
+        }
+
+        if state.0 == maze.end {
+            let mut states = vec![state];
+            while let Some(parent) = parents.get(states.last().unwrap()) {
+                states.push(*parent);
+            }
+            states.reverse();
+            return Some(Route {
+                states,
+                score: candidate.score,
+            });
+        }
+
+        for (position, direction, cost) in rules.next_states(maze, &state.0, &state.1) {
+            let next_state = (position, direction);
+            let Some(score_to_end) = scores_to_end.get(&next_state) else {
+                // the end can't be reached from there
+                continue;
+            };
+            if smallest_scores.contains_key(&next_state)
+                || removed_states.contains(&next_state)
+                || removed_transitions.contains(&(state, next_state))
+            {
+                continue;
+            }
+
+            let score = candidate.score + cost;
+            if tentative_scores
+                .get(&next_state)
+                .is_some_and(|(tentative_score, _)| *tentative_score <= score)
+            {
+                continue;
+            }
+            tentative_scores.insert(next_state, (score, state));
+            queue.push(RouteCandidate {
+                estimated_score: score + score_to_end,
+                score,
+                state: next_state,
+            });
+        }
+    }
+
+    None
+}
+
+// Yen's algorithm: the k cheapest loopless routes, cheapest first
+pub fn find_k_shortest_routes(maze: &Maze, rules: &MazeRules, k: usize) -> Vec<Route> {
+    let source = (maze.start, rules.start_direction);
+    let scores_to_end = reverse_dijkstra(maze, rules);
+    let Some(shortest) = find_shortest_route(
+        maze,
+        rules,
+        &scores_to_end,
+        source,
+        &HashSet::new(),
+        &HashSet::new(),
+    ) else {
+        return vec![];
+    };
+
+    let mut routes = vec![shortest];
+    let mut candidates: Vec<Route> = vec![];
+
+    while routes.len() < k {
+        let previous = routes.last().unwrap().clone();
+
+        for spur_index in 0..previous.states.len() - 1 {
+            let spur_state = previous.states[spur_index];
+            let root = &previous.states[..=spur_index];
+
+            // Don't find again the routes already sharing this root
+            let removed_transitions = routes
+                .iter()
+                .filter(|route| {
+                    route.states.len() > spur_index + 1 && route.states[..=spur_index] == *root
+                })
+                .map(|route| (route.states[spur_index], route.states[spur_index + 1]))
+                .collect::<HashSet<_>>();
+            // Keep the routes loopless
+            let removed_states = root[..spur_index].iter().copied().collect::<HashSet<_>>();
+
+            if let Some(spur) = find_shortest_route(
+                maze,
+                rules,
+                &scores_to_end,
+                spur_state,
+                &removed_states,
+                &removed_transitions,
+            ) {
+                let root_score = root
+                    .windows(2)
+                    .map(|pair| transition_cost(maze, rules, &pair[0], &pair[1]))
+                    .sum::<usize>();
+                let mut states = root[..spur_index].to_vec();
+                states.extend(spur.states);
+
+                let route = Route {
+                    states,
+                    score: root_score + spur.score,
+                };
+                if !routes.contains(&route) && !candidates.contains(&route) {
+                    candidates.push(route);
+                }
+            }
+        }
+
+        let Some(best_index) = (0..candidates.len()).min_by_key(|i| candidates[*i].score) else {
+            break;
+        };
+        routes.push(candidates.swap_remove(best_index));
+    }
+
+    routes
+}
+
+// Smallest score from each state to the end tile, walking the transitions backward
+fn reverse_dijkstra(maze: &Maze, rules: &MazeRules) -> SmallestScoresByTile {
+    let mut queue = rules
+        .directions()
+        .iter()
+        .map(|direction| TileCandidateScore {
+            position: maze.end,
+            direction: *direction,
+            score: 0,
+        })
+        .collect::<BinaryHeap<_>>();
+
+    let mut smallest_scores: SmallestScoresByTile = HashMap::new();
+
+    while let Some(candidate) = queue.pop() {
+        if smallest_scores.contains_key(&(candidate.position, candidate.direction)) {
+            continue;
+        }
+        smallest_scores.insert((candidate.position, candidate.direction), candidate.score);
+
+        for (position, direction, cost) in
+            rules.previous_states(maze, &candidate.position, &candidate.direction)
+        {
+            queue.push(TileCandidateScore {
+                position,
+                direction,
+                score: candidate.score + cost,
+            });
+        }
+    }
+
+    smallest_scores
+}
+
+// Tiles on any path scoring at most `slack` more than the best one
+// With a slack of 0, these are the best paths tiles of part 2
+pub fn find_tiles_within_slack(
+    maze: &Maze,
+    rules: &MazeRules,
+    smallest_scores: &SmallestScoresByTile,
+    slack: usize,
+) -> HashSet<Position> {
+    let scores_to_end = reverse_dijkstra(maze, rules);
+    let best_score = scores_to_end[&(maze.start, rules.start_direction)];
+
+    smallest_scores
+        .iter()
+        .filter(|(state, score)| {
+            scores_to_end
+                .get(state)
+                .is_some_and(|score_to_end| *score + score_to_end <= best_score + slack)
+        })
+        .map(|((position, _), _)| *position)
+        .collect()
+}
@@ -1,5 +1,6 @@
 use crate::read_input;
-use std::ops::Div;
+use num_bigint::BigUint;
+use std::fmt;
 
 #[derive(Clone)]
 struct Instruction {
@@ -7,91 +8,246 @@ struct Instruction {
     operand: u8,
 }
 
+#[derive(Debug)]
+enum ComputerError {
+    InvalidOpcode { pointer: usize, opcode: u8 },
+    InvalidComboOperand { pointer: usize, operand: u8 },
+    StepLimitExceeded { max_steps: usize },
+}
+
+impl fmt::Display for ComputerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComputerError::InvalidOpcode { pointer, opcode } => {
+                write!(f, "invalid opcode {} at address {}", opcode, pointer)
+            }
+            ComputerError::InvalidComboOperand { pointer, operand } => {
+                write!(
+                    f,
+                    "invalid combo operand {} at address {}",
+                    operand, pointer
+                )
+            }
+            ComputerError::StepLimitExceeded { max_steps } => {
+                write!(f, "program did not halt within {} steps", max_steps)
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
 struct Computer {
-    register_a: usize,
-    register_b: usize,
-    register_c: usize,
-    program: Vec<Instruction>,
+    register_a: BigUint,
+    register_b: BigUint,
+    register_c: BigUint,
+    program: Vec<u8>,
 }
 
 impl Computer {
-    fn get_combo_operand_value(&self, operand: u8) -> usize {
+    fn get_combo_operand_value(
+        &self,
+        pointer: usize,
+        operand: u8,
+    ) -> Result<BigUint, ComputerError> {
         match operand {
-            0..=3 => operand.into(),
-            4 => self.register_a,
-            5 => self.register_b,
-            6 => self.register_c,
-            _ => panic!("Unsupported combo operand"),
+            0..=3 => Ok(BigUint::from(operand)),
+            4 => Ok(self.register_a.clone()),
+            5 => Ok(self.register_b.clone()),
+            6 => Ok(self.register_c.clone()),
+            _ => Err(ComputerError::InvalidComboOperand { pointer, operand }),
         }
     }
 
-    pub fn run_program(&mut self) -> Vec<u8> {
+    // Dividing by 2^n is a right shift; any shift wider than usize clears A.
+    fn divide_register_a(&self, pointer: usize, operand: u8) -> Result<BigUint, ComputerError> {
+        let exponent = self.get_combo_operand_value(pointer, operand)?;
+        Ok(match usize::try_from(&exponent) {
+            Ok(shift) => &self.register_a >> shift,
+            Err(_) => BigUint::ZERO,
+        })
+    }
+
+    // The instruction pointer is byte-addressed: jumps may land on odd
+    // addresses, and reading an opcode or operand past the end halts.
+    fn run_program(&mut self, max_steps: Option<usize>) -> Result<Vec<u8>, ComputerError> {
         let mut pointer = 0;
+        let mut steps = 0;
         let mut output = Vec::new();
 
-        while pointer < self.program.len() {
-            let instruction = &self.program[pointer];
+        while pointer + 1 < self.program.len() {
+            if let Some(max_steps) = max_steps.filter(|&max_steps| steps >= max_steps) {
+                return Err(ComputerError::StepLimitExceeded { max_steps });
+            }
+            steps += 1;
+
+            let instruction = Instruction {
+                opcode: self.program[pointer],
+                operand: self.program[pointer + 1],
+            };
             let mut has_jumped = false;
             match instruction.opcode {
                 0 => {
-                    self.register_a = self.register_a.div(
-                        2_usize.pow(
-                            self.get_combo_operand_value(instruction.operand)
-                                .try_into()
-                                .unwrap(),
-                        ),
-                    );
+                    self.register_a = self.divide_register_a(pointer, instruction.operand)?;
                 }
                 1 => {
-                    self.register_b ^= usize::from(instruction.operand);
+                    self.register_b ^= BigUint::from(instruction.operand);
                 }
                 2 => {
-                    self.register_b = self.get_combo_operand_value(instruction.operand) % 8;
+                    self.register_b =
+                        self.get_combo_operand_value(pointer, instruction.operand)? % 8_u8;
                 }
                 3 => {
-                    if self.register_a != 0 {
+                    if self.register_a != BigUint::ZERO {
                         has_jumped = true;
-                        pointer = instruction.operand as usize / 2;
+                        pointer = instruction.operand as usize;
                     }
                 }
                 4 => {
-                    self.register_b ^= self.register_c;
+                    self.register_b ^= &self.register_c;
                 }
                 5 => {
-                    output.push((self.get_combo_operand_value(instruction.operand) % 8) as u8);
+                    let value = self.get_combo_operand_value(pointer, instruction.operand)? % 8_u8;
+                    output.push(u8::try_from(&value).unwrap());
                 }
                 6 => {
-                    self.register_b = self.register_a.div(
-                        2_usize.pow(
-                            self.get_combo_operand_value(instruction.operand)
-                                .try_into()
-                                .unwrap(),
-                        ),
-                    );
+                    self.register_b = self.divide_register_a(pointer, instruction.operand)?;
                 }
                 7 => {
-                    self.register_c = self.register_a.div(
-                        2_usize.pow(
-                            self.get_combo_operand_value(instruction.operand)
-                                .try_into()
-                                .unwrap(),
-                        ),
-                    );
+                    self.register_c = self.divide_register_a(pointer, instruction.operand)?;
                 }
-                _ => panic!("opcode not supported"),
+                opcode => return Err(ComputerError::InvalidOpcode { pointer, opcode }),
             }
 
             if !has_jumped {
-                pointer += 1;
+                pointer += 2;
             }
         }
 
-        output
+        Ok(output)
+    }
+}
+
+#[derive(Debug)]
+enum QuineSearchError {
+    MissingSingleAdv3,
+    MissingSingleOut,
+    MissingFinalJnz0,
+    NoSolution,
+    Computer(ComputerError),
+}
+
+impl fmt::Display for QuineSearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuineSearchError::MissingSingleAdv3 => {
+                write!(
+                    f,
+                    "program must contain exactly one `adv 3` and no other `adv`"
+                )
+            }
+            QuineSearchError::MissingSingleOut => {
+                write!(f, "program must contain exactly one `out` per loop")
+            }
+            QuineSearchError::MissingFinalJnz0 => {
+                write!(f, "program must end with its only jump, a `jnz 0`")
+            }
+            QuineSearchError::NoSolution => {
+                write!(f, "no value of register A makes the program output itself")
+            }
+            QuineSearchError::Computer(e) => write!(f, "{}", e),
+        }
     }
 }
 
-fn parse_register(s: &str) -> usize {
-    s.split_once(": ").unwrap().1.parse::<usize>().unwrap()
+impl From<ComputerError> for QuineSearchError {
+    fn from(e: ComputerError) -> Self {
+        QuineSearchError::Computer(e)
+    }
+}
+
+impl Computer {
+    fn instructions(&self) -> Vec<Instruction> {
+        self.program
+            .chunks(2)
+            .filter(|chunk| chunk.len() == 2)
+            .map(|chunk| Instruction {
+                opcode: chunk[0],
+                operand: chunk[1],
+            })
+            .collect()
+    }
+
+    fn check_quine_loop_shape(&self) -> Result<(), QuineSearchError> {
+        let instructions = self.instructions();
+        let count_opcode = |opcode: u8| {
+            instructions
+                .iter()
+                .filter(|instruction| instruction.opcode == opcode)
+                .count()
+        };
+
+        let has_single_adv_3 = count_opcode(0) == 1
+            && instructions
+                .iter()
+                .any(|instruction| instruction.opcode == 0 && instruction.operand == 3);
+        if !has_single_adv_3 {
+            return Err(QuineSearchError::MissingSingleAdv3);
+        }
+
+        if count_opcode(5) != 1 {
+            return Err(QuineSearchError::MissingSingleOut);
+        }
+
+        let ends_with_jnz_0 = self.program.len().is_multiple_of(2)
+            && instructions
+                .last()
+                .is_some_and(|instruction| instruction.opcode == 3 && instruction.operand == 0);
+        if count_opcode(3) != 1 || !ends_with_jnz_0 {
+            return Err(QuineSearchError::MissingFinalJnz0);
+        }
+
+        Ok(())
+    }
+
+    fn run_with_register_a(&self, register_a: BigUint) -> Result<Vec<u8>, ComputerError> {
+        let mut computer = self.clone();
+        computer.register_a = register_a;
+        computer.run_program(None)
+    }
+
+    // Each loop iteration outputs one value then shifts A right by 3 bits, so the
+    // last output only depends on the top 3 bits of A. Build A from the top down,
+    // keeping every candidate whose run reproduces the tail of the program.
+    fn find_quine_register_a(&self) -> Result<BigUint, QuineSearchError> {
+        self.check_quine_loop_shape()?;
+
+        let targets = &self.program;
+        let mut a_candidates = [BigUint::ZERO].to_vec();
+
+        for length in 1..=targets.len() {
+            let expected_tail = &targets[targets.len() - length..];
+            let mut next_a_candidates = Vec::new();
+            for candidate in a_candidates.iter() {
+                for xxx in 0..8_u8 {
+                    let a = (candidate << 3_usize) | BigUint::from(xxx);
+                    if self.run_with_register_a(a.clone())? == expected_tail {
+                        next_a_candidates.push(a);
+                    }
+                }
+            }
+
+            a_candidates = next_a_candidates;
+        }
+
+        a_candidates
+            .into_iter()
+            .min()
+            .ok_or(QuineSearchError::NoSolution)
+    }
+}
+
+fn parse_register(s: &str) -> BigUint {
+    s.split_once(": ").unwrap().1.parse::<BigUint>().unwrap()
 }
 
 fn init_computer() -> Computer {
@@ -110,55 +266,31 @@ fn init_computer() -> Computer {
             .1
             .split(",")
             .map(|v| v.parse::<u8>().unwrap())
-            .collect::<Vec<u8>>()
-            .chunks(2)
-            .map(|chunk| Instruction {
-                opcode: chunk[0],
-                operand: chunk[1],
-            })
             .collect(),
     }
 }
 
+const MAX_STEPS: usize = 1_000_000;
+
 pub fn run_part_1() {
     let mut computer = init_computer();
-    let output = computer.run_program();
-    println!(
-        "{}",
-        output
-            .iter()
-            .map(|v| v.to_string())
-            .collect::<Vec<String>>()
-            .join(",")
-    )
-}
-
-fn run_instructions(a: usize) -> usize {
-    let b = (a % 8) ^ 1;
-    let c = a.div(2_usize.pow(b as u32));
-    ((b ^ c) ^ 6) % 8
+    match computer.run_program(Some(MAX_STEPS)) {
+        Ok(output) => println!(
+            "{}",
+            output
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        ),
+        Err(e) => eprintln!("Program failed: {}", e),
+    }
 }
 
 pub fn run_part_2() {
-    let mut targets = [2, 4, 1, 1, 7, 5, 4, 0, 0, 3, 1, 6, 5, 5, 3, 0];
-    targets.reverse();
-
-    let mut a_candidates = [0].to_vec();
-
-    for target in targets {
-        let mut next_a_candidates = Vec::new();
-        for xxx in 0..8 {
-            for candidate in a_candidates.iter() {
-                let a = candidate << 3 | xxx;
-                let output = run_instructions(a);
-                if output == target {
-                    next_a_candidates.push(a);
-                }
-            }
-        }
-
-        a_candidates = next_a_candidates;
+    let computer = init_computer();
+    match computer.find_quine_register_a() {
+        Ok(a) => println!("{}", a),
+        Err(e) => eprintln!("Cannot search for a quine: {}", e),
     }
-
-    println!("{}", a_candidates.iter().min().unwrap());
 }
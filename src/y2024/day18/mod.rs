@@ -1,13 +1,22 @@
-use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::{
+    collections::{BinaryHeap, HashMap, HashSet},
+    time::Instant,
+};
 
-use crate::read_input;
+use union_find::UnionFind;
+
+use crate::{option_value, read_input};
+
+mod union_find;
 
 #[derive(Clone)]
 struct MemorySpace {
     is_corrupted: bool,
 }
 
+// Indexed by row then column
 type Memory = Vec<Vec<MemorySpace>>;
+// (x, y): x is the column, y is the row, as in the puzzle input
 type Coordinate = (usize, usize);
 
 #[derive(PartialEq, Eq, Debug)]
@@ -33,13 +42,60 @@ impl PartialOrd for PathFindingMemorySpace {
     }
 }
 
+// How to find the first byte cutting the exit off
+#[derive(Clone, Copy, Debug)]
+pub enum BlockingStrategy {
+    // Drop the bytes one by one, finding a new path only when one lands on the current path
+    Replan,
+    // Binary search over the number of fallen bytes
+    BinarySearch,
+    // Start with every byte fallen, and remove them backward until the exit is connected again
+    UnionFind,
+}
+
+impl BlockingStrategy {
+    const ALL: [BlockingStrategy; 3] = [
+        BlockingStrategy::Replan,
+        BlockingStrategy::BinarySearch,
+        BlockingStrategy::UnionFind,
+    ];
+
+    pub fn parse(strategy: &str) -> Option<BlockingStrategy> {
+        match strategy {
+            "replan" => Some(BlockingStrategy::Replan),
+            "binary-search" => Some(BlockingStrategy::BinarySearch),
+            "union-find" => Some(BlockingStrategy::UnionFind),
+            _ => None,
+        }
+    }
+}
+
 struct Computer {
     memory: Memory,
+    memory_size: usize,
     fallen_bytes: Vec<Coordinate>,
+    start: Coordinate,
+    exit: Coordinate,
 }
 
 impl Computer {
-    pub fn new(memory_size: usize, fallen_bytes: Vec<Coordinate>) -> Self {
+    pub fn new(
+        memory_size: usize,
+        fallen_bytes: Vec<Coordinate>,
+        start: Coordinate,
+        exit: Coordinate,
+    ) -> Self {
+        let is_inside = |(x, y): &Coordinate| *x < memory_size && *y < memory_size;
+        assert!(
+            is_inside(&start),
+            "Start {:?} is outside of the memory",
+            start
+        );
+        assert!(is_inside(&exit), "Exit {:?} is outside of the memory", exit);
+        if let Some(byte) = fallen_bytes.iter().find(|byte| !is_inside(byte)) {
+            panic!("Byte {:?} falls outside of the memory", byte);
+        }
+
         Self {
             memory: vec![
                 vec![
@@ -52,22 +108,34 @@ impl Computer {
                 memory_size
             ]
             .to_vec(),
+            memory_size,
             fallen_bytes,
+            start,
+            exit,
         }
     }
 
+    fn is_corrupted(&self, (x, y): Coordinate) -> bool {
+        self.memory[y][x].is_corrupted
+    }
+
+    fn set_corrupted(&mut self, (x, y): Coordinate, is_corrupted: bool) {
+        self.memory[y][x].is_corrupted = is_corrupted;
+    }
+
     pub fn run_simulation(&mut self, nb_rounds: usize) {
         for i in 0..nb_rounds {
-            self.memory[self.fallen_bytes[i].1][self.fallen_bytes[i].0].is_corrupted = true;
+            self.set_corrupted(self.fallen_bytes[i], true);
         }
     }
 
     fn extract_shortest_path(
         predecessors: &HashMap<Coordinate, Coordinate>,
+        exit: Coordinate,
     ) -> HashSet<Coordinate> {
         let mut coordinates = HashSet::new();
 
-        let mut predecessor = Some((MEMORY_SIZE - 1, MEMORY_SIZE - 1));
+        let mut predecessor = Some(exit);
 
         while let Some(p) = predecessor {
             coordinates.insert(p);
@@ -78,15 +146,13 @@ impl Computer {
     }
 
     pub fn run_simulation_until_blocked(&mut self, start_at: usize) -> Coordinate {
-        for i in 0..start_at {
-            self.memory[self.fallen_bytes[i].0][self.fallen_bytes[i].1].is_corrupted = true;
-        }
+        self.run_simulation(start_at);
 
         let mut shortest_path = self.find_shortest_path();
         let mut i = start_at;
 
         while i < self.fallen_bytes.len() {
-            self.memory[self.fallen_bytes[i].0][self.fallen_bytes[i].1].is_corrupted = true;
+            self.set_corrupted(self.fallen_bytes[i], true);
             if let Some(path) = &shortest_path {
                 if path.contains(&self.fallen_bytes[i]) {
                     shortest_path = self.find_shortest_path();
@@ -103,9 +169,145 @@ impl Computer {
         panic!("No blocking byte found");
     }
 
+    fn reset_memory(&mut self) {
+        self.memory
+            .iter_mut()
+            .flatten()
+            .for_each(|space| space.is_corrupted = false);
+    }
+
+    fn drop_bytes(&mut self, nb_bytes: usize) {
+        self.reset_memory();
+        self.run_simulation(nb_bytes);
+    }
+
+    // Smallest number of fallen bytes blocking the exit is searched between start_at and all of them
+    // The exit must still be reachable after start_at bytes, so the blocking byte comes after them
+    pub fn run_binary_search_until_blocked(&mut self, start_at: usize) -> Coordinate {
+        let (mut low, mut high) = (start_at, self.fallen_bytes.len());
+
+        self.drop_bytes(high);
+        if self.find_shortest_path().is_some() {
+            panic!("No blocking byte found");
+        }
+
+        while low < high {
+            let middle = (low + high) / 2;
+            self.drop_bytes(middle);
+            if self.find_shortest_path().is_some() {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+
+        self.fallen_bytes[low - 1]
+    }
+
+    // Adjacent spaces inside the memory, corrupted or not
+    fn neighbours(&self, (x, y): Coordinate) -> impl Iterator<Item = Coordinate> {
+        let directions: [(isize, isize); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+        let memory_size = self.memory_size;
+
+        directions.into_iter().filter_map(move |(dx, dy)| {
+            let target = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+            (target.0 < memory_size && target.1 < memory_size).then_some(target)
+        })
+    }
+
+    fn index(&self, (x, y): Coordinate) -> usize {
+        y * self.memory_size + x
+    }
+
+    // Joins a free space with its free neighbours
+    fn connect_space(&self, sets: &mut UnionFind, coordinate: Coordinate) {
+        for neighbour in self.neighbours(coordinate) {
+            if !self.is_corrupted(neighbour) {
+                sets.union(self.index(coordinate), self.index(neighbour));
+            }
+        }
+    }
+
+    // All the bytes fall, then they're removed from the last one down to the one after start_at
+    // The byte whose removal connects the start and the exit is the blocking one
+    pub fn run_union_find_until_blocked(&mut self, start_at: usize) -> Coordinate {
+        self.drop_bytes(self.fallen_bytes.len());
+
+        let mut sets = UnionFind::new(self.memory_size * self.memory_size);
+        let start = self.index(self.start);
+        let exit = self.index(self.exit);
+
+        for y in 0..self.memory_size {
+            for x in 0..self.memory_size {
+                if !self.is_corrupted((x, y)) {
+                    self.connect_space(&mut sets, (x, y));
+                }
+            }
+        }
+        if sets.connected(start, exit) {
+            panic!("No blocking byte found");
+        }
+
+        // Several bytes may fall on the same space, it's only freed once they're all removed
+        let mut nb_falls: HashMap<Coordinate, usize> = HashMap::new();
+        for byte in &self.fallen_bytes {
+            *nb_falls.entry(*byte).or_default() += 1;
+        }
+
+        for i in (start_at..self.fallen_bytes.len()).rev() {
+            let byte = self.fallen_bytes[i];
+            let remaining_falls = nb_falls.get_mut(&byte).unwrap();
+            *remaining_falls -= 1;
+            if *remaining_falls > 0 {
+                continue;
+            }
+            self.set_corrupted(byte, false);
+            self.connect_space(&mut sets, byte);
+
+            if sets.connected(start, exit) {
+                return byte;
+            }
+        }
+
+        unreachable!(
+            "The start and the exit are connected once the bytes after start_at are removed"
+        )
+    }
+
+    // The bytes before start_at are assumed not to block the exit, which is checked first
+    pub fn find_blocking_byte(
+        &mut self,
+        strategy: BlockingStrategy,
+        start_at: usize,
+    ) -> Coordinate {
+        assert!(
+            start_at <= self.fallen_bytes.len(),
+            "Only {} bytes fall, {} can't have fallen already",
+            self.fallen_bytes.len(),
+            start_at
+        );
+        self.drop_bytes(start_at);
+        if self.find_shortest_path().is_none() {
+            panic!("The exit is already blocked after {} bytes", start_at);
+        }
+
+        match strategy {
+            BlockingStrategy::Replan => {
+                self.reset_memory();
+                self.run_simulation_until_blocked(start_at)
+            }
+            BlockingStrategy::BinarySearch => self.run_binary_search_until_blocked(start_at),
+            BlockingStrategy::UnionFind => self.run_union_find_until_blocked(start_at),
+        }
+    }
+
     pub fn find_shortest_path(&self) -> Option<HashSet<Coordinate>> {
+        if self.is_corrupted(self.start) {
+            return None;
+        }
+
         let start = PathFindingMemorySpace {
-            coordinate: (0, 0),
+            coordinate: self.start,
             predecessor: None,
             score: 0,
         };
@@ -125,40 +327,45 @@ impl Computer {
                 predecessors.insert(space.coordinate, p);
             }
 
-            if space.coordinate == (MEMORY_SIZE - 1, MEMORY_SIZE - 1) {
-                return Some(Self::extract_shortest_path(&predecessors));
+            if space.coordinate == self.exit {
+                return Some(Self::extract_shortest_path(&predecessors, self.exit));
             }
 
-            let directions: [(isize, isize); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
-
-            directions.iter().for_each(|direction| {
-                let target = (
-                    space.coordinate.0 as isize + direction.0,
-                    space.coordinate.1 as isize + direction.1,
-                );
-
-                if target.0 < 0
-                    || target.1 < 0
-                    || target.0 >= MEMORY_SIZE as isize
-                    || target.1 >= MEMORY_SIZE as isize
-                {
-                    return;
-                }
-
-                if self.memory[target.0 as usize][target.1 as usize].is_corrupted {
-                    return;
+            for target in self.neighbours(space.coordinate) {
+                if self.is_corrupted(target) {
+                    continue;
                 }
 
                 queue.push(PathFindingMemorySpace {
-                    coordinate: (target.0 as usize, target.1 as usize),
+                    coordinate: target,
                     predecessor: Some(space.coordinate),
                     score: space.score + 1,
                 })
-            });
+            }
         }
 
         None
     }
+
+    // Corrupted spaces as #, the path as O
+    pub fn render(&self, path: Option<&HashSet<Coordinate>>) -> String {
+        (0..self.memory_size)
+            .map(|y| {
+                (0..self.memory_size)
+                    .map(|x| {
+                        if self.is_corrupted((x, y)) {
+                            '#'
+                        } else if path.is_some_and(|path| path.contains(&(x, y))) {
+                            'O'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>()
+                    + "\n"
+            })
+            .collect()
+    }
 }
 
 fn build_computer() -> Computer {
@@ -166,25 +373,36 @@ fn build_computer() -> Computer {
         .unwrap()
         .map_while(Result::ok)
         .map(|line| {
-            let (s_row, s_col) = line.split_once(",").unwrap();
-            (
-                s_row.parse::<usize>().unwrap(),
-                s_col.parse::<usize>().unwrap(),
-            )
+            let (s_x, s_y) = line.split_once(",").unwrap();
+            (s_x.parse::<usize>().unwrap(), s_y.parse::<usize>().unwrap())
         })
         .collect();
 
-    Computer::new(MEMORY_SIZE, input)
+    Computer::new(
+        MEMORY_SIZE,
+        input,
+        (0, 0),
+        (MEMORY_SIZE - 1, MEMORY_SIZE - 1),
+    )
 }
 
 const MEMORY_SIZE: usize = 71;
 const SIMULATION_ROUNDS: usize = 1024;
 
-pub fn run_part_1() {
+// --step N lets N bytes fall instead of 1024, --show prints the memory and the shortest path
+pub fn run_part_1(options: &[String]) {
+    let nb_rounds = option_value(options, "--step").map_or(SIMULATION_ROUNDS, |nb_rounds| {
+        nb_rounds.parse().expect("--step expects a number of bytes")
+    });
+
     let mut computer = build_computer();
-    computer.run_simulation(SIMULATION_ROUNDS);
+    computer.run_simulation(nb_rounds.min(computer.fallen_bytes.len()));
     let shortest_path = computer.find_shortest_path();
 
+    if options.iter().any(|option| option == "--show") {
+        print!("{}", computer.render(shortest_path.as_ref()));
+    }
+
     if let Some(coordinates) = shortest_path {
         println!("{}", coordinates.len() - 1);
     } else {
@@ -192,8 +410,34 @@ pub fn run_part_1() {
     }
 }
 
-pub fn run_part_2() {
+// --strategy replan|binary-search|union-find picks how the blocking byte is found
+// --bench times every strategy and checks they agree
+pub fn run_part_2(options: &[String]) {
     let mut computer = build_computer();
-    let blocking_byte = computer.run_simulation_until_blocked(SIMULATION_ROUNDS);
+
+    if options.iter().any(|option| option == "--bench") {
+        let mut blocking_bytes = BlockingStrategy::ALL.iter().map(|strategy| {
+            let start = Instant::now();
+            let blocking_byte = computer.find_blocking_byte(*strategy, SIMULATION_ROUNDS);
+            println!(
+                "{:?}: {:?} in {:?}",
+                strategy,
+                blocking_byte,
+                start.elapsed()
+            );
+            blocking_byte
+        });
+        let blocking_byte = blocking_bytes.next().unwrap();
+        assert!(blocking_bytes.all(|other| other == blocking_byte));
+        return;
+    }
+
+    let strategy =
+        option_value(options, "--strategy").map_or(BlockingStrategy::Replan, |strategy| {
+            BlockingStrategy::parse(strategy)
+                .expect("--strategy expects replan, binary-search or union-find")
+        });
+
+    let blocking_byte = computer.find_blocking_byte(strategy, SIMULATION_ROUNDS);
     println!("{:?}", blocking_byte);
 }
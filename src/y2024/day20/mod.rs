@@ -1,5 +1,5 @@
-use crate::read_input;
-use std::collections::{BinaryHeap, HashMap, HashSet};
+use crate::{option_value, read_input};
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
 
 type Position = (usize, usize);
 
@@ -35,6 +35,31 @@ struct ReachableCell {
     distance: usize,
 }
 
+// Going through the walls from start to end saves `saving` picoseconds
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Cheat {
+    pub start: Position,
+    pub end: Position,
+    pub saving: usize,
+}
+
+pub enum CheatAlgorithm {
+    // Dijkstra from both ends, then every track cell against every cell in reach
+    Grid,
+    // Walks the single corridor once, then compares positions along it
+    Track,
+}
+
+impl CheatAlgorithm {
+    pub fn parse(algorithm: &str) -> Option<CheatAlgorithm> {
+        match algorithm {
+            "grid" => Some(CheatAlgorithm::Grid),
+            "track" => Some(CheatAlgorithm::Track),
+            _ => None,
+        }
+    }
+}
+
 const DIRECTIONS: [(isize, isize); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
 const MIN_SAVE_FOR_QUALIFIED_CHEAT: usize = 100;
 const PART_1_CHEAT_DISTANCE: usize = 2;
@@ -78,16 +103,8 @@ impl RaceCondition {
                     continue;
                 }
 
-                let raw_distance = if target_row > from_row {
-                    target_row - from_row
-                } else {
-                    from_row - target_row
-                };
-                let col_distance = if target_col > from_col {
-                    target_col - from_col
-                } else {
-                    from_col - target_col
-                };
+                let raw_distance = target_row.abs_diff(from_row);
+                let col_distance = target_col.abs_diff(from_col);
                 let manhattan_dist = raw_distance + col_distance;
 
                 if manhattan_dist <= max_distance {
@@ -102,16 +119,16 @@ impl RaceCondition {
         reachable_cells
     }
 
-    fn count_qualified_cheats(
-        &mut self,
+    fn find_cheats_on_grid(
+        &self,
         shortest_distance_without_cheat: usize,
         min_save: usize,
         cheat_distance: usize,
-    ) -> usize {
+    ) -> Vec<Cheat> {
         let shortest_from_start = self.dijkstra(self.start, self.end);
         let shortest_from_end = self.dijkstra(self.end, self.start);
 
-        let mut qualified_cheats: HashSet<(Position, Position)> = HashSet::new();
+        let mut qualified_cheats = Vec::new();
 
         for r in 1..self.nb_rows - 1 {
             for c in 1..self.nb_cols - 1 {
@@ -133,7 +150,11 @@ impl RaceCondition {
                             if total < shortest_distance_without_cheat
                                 && shortest_distance_without_cheat - total >= min_save
                             {
-                                qualified_cheats.insert(((r, c), reachable_cell.position));
+                                qualified_cheats.push(Cheat {
+                                    start: (r, c),
+                                    end: reachable_cell.position,
+                                    saving: shortest_distance_without_cheat - total,
+                                });
                             }
                         }
                     }
@@ -141,7 +162,84 @@ impl RaceCondition {
             }
         }
 
-        qualified_cheats.len()
+        qualified_cheats
+    }
+
+    // The track is a single corridor without forks, listed from start to end
+    fn trace_track(&self) -> Vec<Position> {
+        let mut track = vec![self.start];
+        let mut previous = None;
+
+        while *track.last().unwrap() != self.end {
+            let current = *track.last().unwrap();
+            let mut next_cells = DIRECTIONS.iter().filter_map(|direction| {
+                let target = (
+                    current.0.checked_add_signed(direction.0)?,
+                    current.1.checked_add_signed(direction.1)?,
+                );
+                (target.0 < self.nb_rows
+                    && target.1 < self.nb_cols
+                    && self.racetrack[target.0][target.1] == Cell::Track
+                    && Some(target) != previous)
+                    .then_some(target)
+            });
+
+            let next = next_cells.next().expect("The track is a dead end");
+            assert!(next_cells.next().is_none(), "The track forks");
+
+            previous = Some(current);
+            track.push(next);
+        }
+
+        track
+    }
+
+    // Along the corridor, a cheat from index i to index j > i saves j - i - distance
+    // The distance changes by at most 1 per step, so cells too far away can be skipped
+    fn find_cheats_along_track(&self, min_save: usize, cheat_distance: usize) -> Vec<Cheat> {
+        let track = self.trace_track();
+        let mut qualified_cheats = Vec::new();
+        // A cheat has to save some time
+        let min_save = min_save.max(1);
+
+        for (i, from) in track.iter().enumerate() {
+            let mut j = i + min_save + 1;
+
+            while j < track.len() {
+                let distance = from.0.abs_diff(track[j].0) + from.1.abs_diff(track[j].1);
+                if distance > cheat_distance {
+                    j += distance - cheat_distance;
+                    continue;
+                }
+
+                if j - i >= distance + min_save {
+                    qualified_cheats.push(Cheat {
+                        start: *from,
+                        end: track[j],
+                        saving: j - i - distance,
+                    });
+                }
+                j += 1;
+            }
+        }
+
+        qualified_cheats
+    }
+
+    pub fn find_cheats(
+        &self,
+        algorithm: &CheatAlgorithm,
+        min_save: usize,
+        cheat_distance: usize,
+    ) -> Vec<Cheat> {
+        match algorithm {
+            CheatAlgorithm::Grid => {
+                let shortest_distance_without_cheat =
+                    self.dijkstra(self.start, self.end)[&self.end];
+                self.find_cheats_on_grid(shortest_distance_without_cheat, min_save, cheat_distance)
+            }
+            CheatAlgorithm::Track => self.find_cheats_along_track(min_save, cheat_distance),
+        }
     }
 
     fn dijkstra(&self, from: Position, to: Position) -> HashMap<Position, usize> {
@@ -222,26 +320,61 @@ fn init_race_condition() -> RaceCondition {
     RaceCondition::new(racetrack, start, end)
 }
 
-pub fn run(cheat_distance: usize) -> usize {
-    let mut race_condition = init_race_condition();
-    let shortest_distance_without_cheat = *race_condition
-        .dijkstra(race_condition.start, race_condition.end)
-        .get(&race_condition.end)
-        .unwrap();
+// Number of cheats by time saved, as in the tables of the puzzle examples
+pub fn cheat_histogram(cheats: &[Cheat]) -> BTreeMap<usize, usize> {
+    let mut histogram = BTreeMap::new();
+    for cheat in cheats {
+        *histogram.entry(cheat.saving).or_default() += 1;
+    }
+    histogram
+}
+
+pub fn cheats_with_saving(cheats: &[Cheat], saving: usize) -> Vec<&Cheat> {
+    cheats
+        .iter()
+        .filter(|cheat| cheat.saving == saving)
+        .collect()
+}
+
+// --min-save N changes the time a cheat must save, 100 by default
+// --algorithm grid|track picks how the cheats are found, track by default
+// --histogram prints the number of cheats by time saved, --list N the cheats saving N
+pub fn run(cheat_distance: usize, options: &[String]) -> usize {
+    let min_save = option_value(options, "--min-save").map_or(MIN_SAVE_FOR_QUALIFIED_CHEAT, |n| {
+        n.parse().expect("--min-save expects a number")
+    });
+    let algorithm = option_value(options, "--algorithm")
+        .map_or(CheatAlgorithm::Track, |algorithm| {
+            CheatAlgorithm::parse(algorithm).expect("--algorithm expects grid or track")
+        });
+
+    let race_condition = init_race_condition();
+    let cheats = race_condition.find_cheats(&algorithm, min_save, cheat_distance);
+
+    if options.iter().any(|option| option == "--histogram") {
+        for (saving, nb_cheats) in cheat_histogram(&cheats) {
+            println!("{nb_cheats} cheats save {saving} picoseconds");
+        }
+    }
+    if let Some(saving) = option_value(options, "--list")
+        .map(|saving| saving.parse().expect("--list expects a number"))
+    {
+        let mut listed_cheats = cheats_with_saving(&cheats, saving);
+        listed_cheats.sort();
+        for cheat in listed_cheats {
+            println!("{:?} -> {:?}", cheat.start, cheat.end);
+        }
+    }
 
-    race_condition.count_qualified_cheats(
-        shortest_distance_without_cheat,
-        MIN_SAVE_FOR_QUALIFIED_CHEAT,
-        cheat_distance,
-    )
+    cheats.len()
 }
 
-pub fn run_part_1() {
-    let nb_qualified_cheats = run(PART_1_CHEAT_DISTANCE);
+pub fn run_part_1(options: &[String]) {
+    let nb_qualified_cheats = run(PART_1_CHEAT_DISTANCE, options);
     println!("Part 1: {nb_qualified_cheats}");
 }
 
-pub fn run_part_2() {
-    let nb_qualified_cheats = run(PART_2_CHEAT_DISTANCE);
+pub fn run_part_2(options: &[String]) {
+    let nb_qualified_cheats = run(PART_2_CHEAT_DISTANCE, options);
     println!("Part 2: {nb_qualified_cheats}");
 }
@@ -26,7 +26,7 @@ impl PartialOrd for DijkstraCandidate {
 pub fn keypad_dijkstra(
     from: &str,
     to: &str,
-    keypad: &HashMap<&'static str, Vec<(Direction, &'static str)>>,
+    keypad: &HashMap<String, Vec<(Direction, String)>>,
 ) -> Vec<String> {
     let start = DijkstraCandidate {
         key: from.to_string(),
@@ -48,7 +48,7 @@ pub fn keypad_dijkstra(
 
         sequences.push(candidate.sequence.clone());
 
-        let directions = keypad.get(&candidate.key.as_str()).unwrap();
+        let directions = keypad.get(&candidate.key).unwrap();
 
         for (dir, target_key) in directions.iter() {
             let mut candidate_sequence = candidate.sequence.clone();
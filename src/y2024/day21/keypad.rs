@@ -1,10 +1,8 @@
 use std::collections::HashMap;
 
-use lazy_static::lazy_static;
-
 use super::dijkstra::keypad_dijkstra;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq)]
 pub enum Direction {
     Up,
     Down,
@@ -21,64 +19,142 @@ impl Direction {
             Direction::Left => "<",
         }
     }
+
+    pub fn from_char(c: char) -> Option<Direction> {
+        match c {
+            '^' => Some(Direction::Up),
+            'v' => Some(Direction::Down),
+            '>' => Some(Direction::Right),
+            '<' => Some(Direction::Left),
+            _ => None,
+        }
+    }
 }
 
-lazy_static! {
-    pub static ref NUMERIC_KEYPAD: HashMap<&'static str, Vec<(Direction, &'static str)>> = {
-        let mut m = HashMap::new();
+// Layouts are rows of single character keys, with a space marking a gap the arm must never
+// point at, so any other character, `#` and `*` included, can be a key
+pub const NUMERIC_KEYPAD: &str = "789
+456
+123
+ 0A";
+
+pub const DIRECTIONAL_KEYPAD: &str = " ^A
+<v>";
 
-        // Top row
-        m.insert("7", vec![(Direction::Right, "8"), (Direction::Down, "4")]);
-        m.insert("8", vec![(Direction::Left, "7"), (Direction::Right, "9"), (Direction::Down, "5")]);
-        m.insert("9", vec![(Direction::Left, "8"), (Direction::Down, "6")]);
+const GAP: char = ' ';
 
-        // Middle row
-        m.insert("4", vec![(Direction::Up, "7"), (Direction::Right, "5"), (Direction::Down, "1")]);
-        m.insert("5", vec![(Direction::Up, "8"), (Direction::Left, "4"), (Direction::Right, "6"), (Direction::Down, "2")]);
-        m.insert("6", vec![(Direction::Up, "9"), (Direction::Left, "5"), (Direction::Down, "3")]);
+// The key pressing the key the arm points at on the keypad below
+pub const PRESS_KEY: char = 'A';
 
-        // Bottom row
-        m.insert("1", vec![(Direction::Up, "4"), (Direction::Right, "2")]);
-        m.insert("2", vec![(Direction::Up, "5"), (Direction::Left, "1"), (Direction::Right, "3"), (Direction::Down, "0")]);
-        m.insert("3", vec![(Direction::Up, "6"), (Direction::Left, "2"), (Direction::Down, "A")]);
+pub struct Keypad {
+    keys: HashMap<String, Vec<(Direction, String)>>,
+    start_key: char,
+}
 
-        // Bottom-most row
-        m.insert("0", vec![(Direction::Up, "2"), (Direction::Right, "A")]);
-        m.insert("A", vec![(Direction::Up, "3"), (Direction::Left, "0")]);
+impl Keypad {
+    // Adjacency is derived from the layout, gaps are simply never linked to
+    // The arm points at `start_key` before typing anything
+    pub fn from_layout(layout: &str, start_key: char) -> Keypad {
+        let grid = layout
+            .lines()
+            .map(|line| line.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let key_at = |row: isize, col: isize| {
+            if row < 0 || col < 0 {
+                return None;
+            }
+            grid.get(row as usize)
+                .and_then(|keys| keys.get(col as usize))
+                .filter(|key| **key != GAP)
+        };
+
+        let mut keys = HashMap::new();
+        for (row, line) in grid.iter().enumerate() {
+            for (col, key) in line.iter().enumerate() {
+                if *key == GAP {
+                    continue;
+                }
+
+                let neighbours = [
+                    (Direction::Up, -1, 0),
+                    (Direction::Down, 1, 0),
+                    (Direction::Left, 0, -1),
+                    (Direction::Right, 0, 1),
+                ]
+                .into_iter()
+                .filter_map(|(direction, d_row, d_col)| {
+                    key_at(row as isize + d_row, col as isize + d_col)
+                        .map(|neighbour| (direction, neighbour.to_string()))
+                })
+                .collect();
+
+                keys.insert(key.to_string(), neighbours);
+            }
+        }
 
-        m
-    };
+        assert!(
+            keys.contains_key(&start_key.to_string()),
+            "The start key {:?} is not on the keypad",
+            start_key
+        );
 
-    pub static ref DIRECTIONAL_KEYPAD: HashMap<&'static str, Vec<(Direction, &'static str)>> = {
-        let mut m = HashMap::new();
+        Keypad { keys, start_key }
+    }
 
-        // Top row
-        m.insert("^", vec![(Direction::Right, "A"), (Direction::Down, "v")]);
-        m.insert("A", vec![(Direction::Left, "^"), (Direction::Down, ">")]);
+    pub fn start_key(&self) -> char {
+        self.start_key
+    }
 
-        // Bottom row
-        m.insert("<", vec![(Direction::Right, "v")]);
-        m.insert("v", vec![(Direction::Up, "^"), (Direction::Left, "<"), (Direction::Right, ">")]);
-        m.insert(">", vec![(Direction::Up, "A"), (Direction::Left, "v")]);
+    pub fn keys(&self) -> Vec<char> {
+        let mut keys = self
+            .keys
+            .keys()
+            .flat_map(|key| key.chars())
+            .collect::<Vec<_>>();
+        keys.sort();
+        keys
+    }
 
-        m
-    };
-}
+    // None when the arm would leave the keypad or point at a gap
+    pub fn move_arm(&self, from: &str, direction: Direction) -> Option<&str> {
+        self.keys
+            .get(from)?
+            .iter()
+            .find(|(d, _)| *d == direction)
+            .map(|(_, key)| key.as_str())
+    }
 
-pub struct Keypad {
-    keys: &'static HashMap<&'static str, Vec<(Direction, &'static str)>>,
-}
+    // Replays presses on this keypad, starting with the arm on the start key, and returns the
+    // keys pushed
+    pub fn type_sequence(&self, sequence: &str) -> Option<String> {
+        let start_key = self.start_key.to_string();
+        let mut arm = start_key.as_str();
+        let mut typed = String::new();
+
+        for c in sequence.chars() {
+            if c == PRESS_KEY {
+                typed.push_str(arm);
+            } else {
+                arm = self.move_arm(arm, Direction::from_char(c)?)?;
+            }
+        }
 
-impl Keypad {
-    pub fn new(keys: &'static HashMap<&'static str, Vec<(Direction, &'static str)>>) -> Keypad {
-        Keypad { keys }
+        Some(typed)
     }
 
     pub fn find_keypad_shortest_sequences(&self, from: &str, to: &str) -> Vec<String> {
-        let mut sequences = keypad_dijkstra(from, to, self.keys);
+        for key in [from, to] {
+            assert!(
+                self.keys.contains_key(key),
+                "{:?} is not on the keypad",
+                key
+            );
+        }
+
+        let mut sequences = keypad_dijkstra(from, to, &self.keys);
         // press A after each sequence
         // the sequence moves the arm from `from` to `to` but then need to push the key with `A`
-        sequences.iter_mut().for_each(|s| s.push('A'));
+        sequences.iter_mut().for_each(|s| s.push(PRESS_KEY));
         sequences
     }
 }
@@ -1,54 +1,74 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, iter::Sum, ops::Mul};
 
-use super::keypad::{Keypad, DIRECTIONAL_KEYPAD, NUMERIC_KEYPAD};
+use super::{
+    keypad::{Keypad, DIRECTIONAL_KEYPAD, NUMERIC_KEYPAD, PRESS_KEY},
+    transition_matrix::TransitionMatrix,
+};
 
-pub struct KeypadLayeringSystem {
+// From this many remaining layers on, the best sequence between two directional keys
+// stays the same however many layers are added on top
+const STABLE_NB_LAYERS: usize = 4;
+
+// Lengths grow exponentially with the number of layers: usize overflows after a few dozen,
+// use BigUint for deeper chains
+pub trait SequenceLength: Clone + Ord + Sum + From<usize> {}
+
+impl<L: Clone + Ord + Sum + From<usize>> SequenceLength for L {}
+
+pub struct KeypadLayeringSystem<L: SequenceLength = usize> {
     numerical_keypad: Keypad,
     directional_keypad: Keypad,
     nb_directional_layers: usize,
-    cache: HashMap<(char, char, usize), usize>,
+    cache: HashMap<(char, char, usize), L>,
 }
 
-impl KeypadLayeringSystem {
-    pub fn new(nb_directional_layers: usize) -> KeypadLayeringSystem {
+impl<L: SequenceLength> KeypadLayeringSystem<L> {
+    pub fn new(nb_directional_layers: usize) -> KeypadLayeringSystem<L> {
+        KeypadLayeringSystem::with_keypads(
+            Keypad::from_layout(NUMERIC_KEYPAD, 'A'),
+            Keypad::from_layout(DIRECTIONAL_KEYPAD, PRESS_KEY),
+            nb_directional_layers,
+        )
+    }
+
+    // The bottom keypad can be any layout with any start key, the directional one must have
+    // the `^v<>A` keys and start on A
+    pub fn with_keypads(
+        numerical_keypad: Keypad,
+        directional_keypad: Keypad,
+        nb_directional_layers: usize,
+    ) -> KeypadLayeringSystem<L> {
+        // the robot typing on the bottom keypad is driven by at least one directional keypad
+        assert!(
+            nb_directional_layers > 0,
+            "At least one directional layer is needed"
+        );
+
+        assert!(
+            directional_keypad.keys() == ['<', '>', 'A', '^', 'v']
+                && directional_keypad.start_key() == PRESS_KEY,
+            "The directional keypad must have the ^v<>A keys and start on A"
+        );
+
         KeypadLayeringSystem {
-            numerical_keypad: Keypad::new(&NUMERIC_KEYPAD),
-            directional_keypad: Keypad::new(&DIRECTIONAL_KEYPAD),
+            numerical_keypad,
+            directional_keypad,
             nb_directional_layers,
             cache: HashMap::new(),
         }
     }
 
-    fn get_shortest_sequence_length_between_keys(&mut self, from: &char, to: &char) -> usize {
+    fn get_shortest_sequence_length_between_keys(&mut self, from: &char, to: &char) -> L {
         let shortest_numerical_keypad_sequences: Vec<String> = self
             .numerical_keypad
             .find_keypad_shortest_sequences(from.to_string().as_str(), to.to_string().as_str());
 
-        let mut shortest_sequence_length = usize::MAX;
-
-        for sequence in shortest_numerical_keypad_sequences {
-            // Put a A in front, because we start from A key
-            let sequence = "A".to_string() + &sequence;
-
-            // Check the length of each sequence at the upper directional layer
-            let length = sequence
-                .chars()
-                .collect::<Vec<_>>()
-                .windows(2)
-                .map(|pair| {
-                    self.apply_directional_layers(
-                        &pair[0],
-                        &pair[1],
-                        self.nb_directional_layers - 1,
-                    )
-                })
-                .sum();
-
-            // Keep track of the shortest sequence length
-            shortest_sequence_length = shortest_sequence_length.min(length);
-        }
-
-        shortest_sequence_length
+        // Check the length of each sequence at the upper directional layer, keep the shortest
+        shortest_numerical_keypad_sequences
+            .into_iter()
+            .map(|sequence| self.get_sequence_length(&sequence, self.nb_directional_layers - 1))
+            .min()
+            .unwrap()
     }
 
     // Recursive approach to apply the directional layers
@@ -59,9 +79,9 @@ impl KeypadLayeringSystem {
         from: &char,
         to: &char,
         remaining_nb_layers: usize,
-    ) -> usize {
+    ) -> L {
         if let Some(cached_value) = self.cache.get(&(*from, *to, remaining_nb_layers)) {
-            return *cached_value;
+            return cached_value.clone();
         }
 
         let shortest_directional_sequences: Vec<String> = self
@@ -73,42 +93,206 @@ impl KeypadLayeringSystem {
             // As it's the last layer, we know for sure these sequences are the shortest one for these layers
             // Because we don't build other sequences on top of them
             // -> We build up the response from there
+            return L::from(
+                shortest_directional_sequences
+                    .iter()
+                    .min_by_key(|s| s.len())
+                    .map_or(0, |s| s.len()),
+            );
+        }
+
+        let shortest_sequence_length = shortest_directional_sequences
+            .into_iter()
+            .map(|sequence| self.get_sequence_length(&sequence, remaining_nb_layers - 1))
+            .min()
+            .unwrap();
+
+        self.cache.insert(
+            (*from, *to, remaining_nb_layers),
+            shortest_sequence_length.clone(),
+        );
+
+        shortest_sequence_length
+    }
+
+    // Length of the top level sequence typing `sequence` on the keypad below the given layer
+    fn get_sequence_length(&mut self, sequence: &str, remaining_nb_layers: usize) -> L {
+        // Put a A in front, because we start from A key
+        (PRESS_KEY.to_string() + sequence)
+            .chars()
+            .collect::<Vec<_>>()
+            .windows(2)
+            .map(|pair| self.apply_directional_layers(&pair[0], &pair[1], remaining_nb_layers))
+            .sum()
+    }
+
+    // Same as apply_directional_layers, but builds one of the shortest sequences
+    // Its length grows exponentially with the number of layers
+    fn build_directional_layers(
+        &mut self,
+        from: &char,
+        to: &char,
+        remaining_nb_layers: usize,
+    ) -> String {
+        let shortest_directional_sequences: Vec<String> = self
+            .directional_keypad
+            .find_keypad_shortest_sequences(from.to_string().as_str(), to.to_string().as_str());
+
+        if remaining_nb_layers == 0 {
             return shortest_directional_sequences
-                .iter()
+                .into_iter()
                 .min_by_key(|s| s.len())
-                .map_or(0, |s| s.len());
+                .unwrap_or_default();
         }
 
-        let mut shortest_sequence_length = usize::MAX;
+        let best_sequence = shortest_directional_sequences
+            .into_iter()
+            .min_by_key(|sequence| self.get_sequence_length(sequence, remaining_nb_layers - 1))
+            .unwrap_or_default();
 
-        for sequence in shortest_directional_sequences {
-            // Put a A in front, because we start from A key
-            let sequence = "A".to_string() + &sequence;
+        self.build_sequence(&best_sequence, remaining_nb_layers - 1)
+    }
 
-            let length = sequence
-                .chars()
-                .collect::<Vec<_>>()
-                .windows(2)
-                .map(|pair| {
-                    self.apply_directional_layers(&pair[0], &pair[1], remaining_nb_layers - 1)
-                })
-                .sum();
+    fn build_sequence(&mut self, sequence: &str, remaining_nb_layers: usize) -> String {
+        (PRESS_KEY.to_string() + sequence)
+            .chars()
+            .collect::<Vec<_>>()
+            .windows(2)
+            .map(|pair| self.build_directional_layers(&pair[0], &pair[1], remaining_nb_layers))
+            .collect()
+    }
 
-            shortest_sequence_length = shortest_sequence_length.min(length);
-        }
+    // One of the shortest top level button sequences typing the code, only meant for a few layers
+    pub fn get_fewest_button_presses_sequence_for_code(&mut self, code: &str) -> String {
+        let keys = (self.numerical_keypad.start_key().to_string() + code)
+            .chars()
+            .collect::<Vec<_>>();
 
-        self.cache
-            .insert((*from, *to, remaining_nb_layers), shortest_sequence_length);
+        keys.windows(2)
+            .map(|pair| {
+                let best_sequence = self
+                    .numerical_keypad
+                    .find_keypad_shortest_sequences(
+                        pair[0].to_string().as_str(),
+                        pair[1].to_string().as_str(),
+                    )
+                    .into_iter()
+                    .min_by_key(|sequence| {
+                        self.get_sequence_length(sequence, self.nb_directional_layers - 1)
+                    })
+                    .unwrap_or_default();
 
-        shortest_sequence_length
+                self.build_sequence(&best_sequence, self.nb_directional_layers - 1)
+            })
+            .collect()
     }
 
-    pub fn get_fewest_number_button_press_for_code(&mut self, code: &str) -> usize {
-        // Put a A in front, because we start from A key on the numerical keypad
-        let keys = ("A".to_string() + code).chars().collect::<Vec<_>>();
+    // Replays a top level sequence through every directional layer down to the numerical keypad
+    // Returns None if an arm points at a gap or leaves its keypad
+    pub fn simulate(&self, sequence: &str) -> Option<String> {
+        let mut typed = sequence.to_string();
+        for _ in 0..self.nb_directional_layers {
+            typed = self.directional_keypad.type_sequence(&typed)?;
+        }
+        self.numerical_keypad.type_sequence(&typed)
+    }
+
+    pub fn get_fewest_number_button_press_for_code(&mut self, code: &str) -> L {
+        // Put the start key in front, because the arm starts there on the numerical keypad
+        let keys = (self.numerical_keypad.start_key().to_string() + code)
+            .chars()
+            .collect::<Vec<_>>();
 
         keys.windows(2)
             .map(|pair| self.get_shortest_sequence_length_between_keys(&pair[0], &pair[1]))
             .sum()
     }
 }
+
+impl<L: SequenceLength + Mul<Output = L>> KeypadLayeringSystem<L> {
+    fn get_directional_pairs(&self) -> Vec<(char, char)> {
+        let keys = self.directional_keypad.keys();
+        keys.iter()
+            .flat_map(|from| keys.iter().map(move |to| (*from, *to)))
+            .collect()
+    }
+
+    // Same lengths as apply_directional_layers for every pair of directional keys,
+    // but past STABLE_NB_LAYERS each extra layer is a multiplication by a fixed transition matrix
+    fn get_directional_layers_lengths(
+        &mut self,
+        remaining_nb_layers: usize,
+    ) -> HashMap<(char, char), L> {
+        let pairs = self.get_directional_pairs();
+
+        if remaining_nb_layers <= STABLE_NB_LAYERS {
+            return pairs
+                .into_iter()
+                .map(|(from, to)| {
+                    let length = self.apply_directional_layers(&from, &to, remaining_nb_layers);
+                    ((from, to), length)
+                })
+                .collect();
+        }
+
+        let stable_lengths = pairs
+            .iter()
+            .map(|(from, to)| self.apply_directional_layers(from, to, STABLE_NB_LAYERS))
+            .collect::<Vec<_>>();
+
+        let cells = pairs
+            .iter()
+            .map(|(from, to)| {
+                let best_sequence = self
+                    .directional_keypad
+                    .find_keypad_shortest_sequences(&from.to_string(), &to.to_string())
+                    .into_iter()
+                    .min_by_key(|sequence| self.get_sequence_length(sequence, STABLE_NB_LAYERS))
+                    .unwrap();
+
+                let sequence_pairs = (PRESS_KEY.to_string() + &best_sequence)
+                    .chars()
+                    .collect::<Vec<_>>()
+                    .windows(2)
+                    .map(|pair| (pair[0], pair[1]))
+                    .collect::<Vec<_>>();
+                pairs
+                    .iter()
+                    .map(|pair| L::from(sequence_pairs.iter().filter(|p| *p == pair).count()))
+                    .collect()
+            })
+            .collect();
+
+        let lengths = TransitionMatrix::new(cells)
+            .pow(remaining_nb_layers - STABLE_NB_LAYERS)
+            .apply(&stable_lengths);
+
+        pairs.into_iter().zip(lengths).collect()
+    }
+
+    // Same result as get_fewest_number_button_press_for_code, in O(log n) for n layers
+    pub fn get_fewest_number_button_press_for_code_closed_form(&mut self, code: &str) -> L {
+        let lengths = self.get_directional_layers_lengths(self.nb_directional_layers - 1);
+        let keys = (self.numerical_keypad.start_key().to_string() + code)
+            .chars()
+            .collect::<Vec<_>>();
+
+        keys.windows(2)
+            .map(|pair| {
+                self.numerical_keypad
+                    .find_keypad_shortest_sequences(&pair[0].to_string(), &pair[1].to_string())
+                    .into_iter()
+                    .map(|sequence| {
+                        (PRESS_KEY.to_string() + &sequence)
+                            .chars()
+                            .collect::<Vec<_>>()
+                            .windows(2)
+                            .map(|pair| lengths[&(pair[0], pair[1])].clone())
+                            .sum::<L>()
+                    })
+                    .min()
+                    .unwrap()
+            })
+            .sum()
+    }
+}
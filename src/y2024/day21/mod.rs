@@ -1,13 +1,17 @@
-use keypad_layering_system::KeypadLayeringSystem;
+use std::ops::Mul;
+
+use keypad_layering_system::{KeypadLayeringSystem, SequenceLength};
+use num_bigint::BigUint;
 
 mod dijkstra;
 mod keypad;
 mod keypad_layering_system;
+mod transition_matrix;
 
 use crate::read_input;
 
-fn run(nb_layers: usize) -> usize {
-    let mut kls = KeypadLayeringSystem::new(nb_layers);
+fn run<L: SequenceLength + Mul<Output = L>>(nb_layers: usize) -> L {
+    let mut kls = KeypadLayeringSystem::<L>::new(nb_layers);
 
     read_input(2024, 21)
         .unwrap()
@@ -16,17 +20,75 @@ fn run(nb_layers: usize) -> usize {
             let s_len = kls.get_fewest_number_button_press_for_code(&code);
             let digit = code[0..3].parse::<usize>().unwrap_or(0);
 
-            s_len * digit
+            s_len * L::from(digit)
         })
         .sum()
 }
 
+// Builds the actual sequences and types them back through every layer
+fn verify_sequences(nb_layers: usize) {
+    let mut kls = KeypadLayeringSystem::<usize>::new(nb_layers);
+
+    for code in read_input(2024, 21).unwrap().map_while(Result::ok) {
+        let sequence = kls.get_fewest_button_presses_sequence_for_code(&code);
+        assert_eq!(
+            sequence.len(),
+            kls.get_fewest_number_button_press_for_code(&code)
+        );
+        assert_eq!(kls.simulate(&sequence), Some(code));
+    }
+}
+
 pub fn run_part_1() {
-    let result = run(2);
+    let result = run::<usize>(2);
     assert_eq!(result, 278748);
+    verify_sequences(2);
 }
 
 pub fn run_part_2() {
-    let result = run(25);
+    let result = run::<usize>(25);
     assert_eq!(result, 337744744231414);
+    assert_eq!(run_closed_form::<usize>(25), result);
+}
+
+fn run_closed_form<L: SequenceLength + Mul<Output = L>>(nb_layers: usize) -> L {
+    let mut kls = KeypadLayeringSystem::<L>::new(nb_layers);
+
+    read_input(2024, 21)
+        .unwrap()
+        .map_while(Result::ok)
+        .map(|code| {
+            let s_len = kls.get_fewest_number_button_press_for_code_closed_form(&code);
+            let digit = code[0..3].parse::<usize>().unwrap_or(0);
+
+            s_len * L::from(digit)
+        })
+        .sum()
+}
+
+// Complexity for any number of directional layers, 100 by default
+// --check first cross-checks the closed form against the memoised recursion up to 100 layers
+pub fn run_deep(options: &[String]) {
+    let nb_layers = options
+        .iter()
+        .find(|option| !option.starts_with("--"))
+        .map_or(100, |nb_layers| {
+            nb_layers
+                .parse::<usize>()
+                .ok()
+                .filter(|nb_layers| *nb_layers > 0)
+                .expect("deep expects a number of directional layers above 0")
+        });
+
+    if options.iter().any(|option| option == "--check") {
+        assert_eq!(run::<BigUint>(25), BigUint::from(337744744231414_usize));
+        for nb_layers in 1..=100 {
+            assert_eq!(
+                run_closed_form::<BigUint>(nb_layers),
+                run::<BigUint>(nb_layers)
+            );
+        }
+    }
+
+    println!("{}", run_closed_form::<BigUint>(nb_layers));
 }
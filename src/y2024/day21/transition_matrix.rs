@@ -0,0 +1,68 @@
+use std::ops::Mul;
+
+use super::keypad_layering_system::SequenceLength;
+
+// Square matrix over (from, to) key pairs: entry (i, j) counts how many times pair j
+// appears in the sequence chosen to go through pair i on the layer above
+pub struct TransitionMatrix<L: SequenceLength> {
+    cells: Vec<Vec<L>>,
+}
+
+impl<L: SequenceLength + Mul<Output = L>> TransitionMatrix<L> {
+    pub fn new(cells: Vec<Vec<L>>) -> Self {
+        TransitionMatrix { cells }
+    }
+
+    fn identity(size: usize) -> Self {
+        TransitionMatrix {
+            cells: (0..size)
+                .map(|i| (0..size).map(|j| L::from(usize::from(i == j))).collect())
+                .collect(),
+        }
+    }
+
+    fn multiply(&self, other: &Self) -> Self {
+        let size = self.cells.len();
+        TransitionMatrix {
+            cells: (0..size)
+                .map(|i| {
+                    (0..size)
+                        .map(|j| {
+                            (0..size)
+                                .map(|k| self.cells[i][k].clone() * other.cells[k][j].clone())
+                                .sum()
+                        })
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    // Exponentiation by squaring: O(log n) matrix multiplications
+    pub fn pow(&self, mut n: usize) -> Self {
+        let mut result = TransitionMatrix::identity(self.cells.len());
+        let mut base = TransitionMatrix::new(self.cells.clone());
+
+        while n > 0 {
+            if n % 2 == 1 {
+                result = result.multiply(&base);
+            }
+            base = base.multiply(&base);
+            n /= 2;
+        }
+
+        result
+    }
+
+    pub fn apply(&self, vector: &[L]) -> Vec<L> {
+        self.cells
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(vector)
+                    .map(|(a, b)| a.clone() * b.clone())
+                    .sum()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,77 @@
+use super::SECRET_BITS;
+
+// Linear map over GF(2)^24: column i is the image of the secret with only bit i set,
+// applying the map XORs the columns of the bits set in the input
+#[derive(Clone, PartialEq, Eq)]
+pub struct BitMatrix {
+    columns: [usize; SECRET_BITS],
+}
+
+impl BitMatrix {
+    // `map` must be linear over GF(2), i.e. only made of shifts, masks and XORs
+    pub fn from_linear_map(map: impl Fn(usize) -> usize) -> Self {
+        BitMatrix {
+            columns: std::array::from_fn(|i| map(1 << i)),
+        }
+    }
+
+    fn identity() -> Self {
+        BitMatrix::from_linear_map(|secret| secret)
+    }
+
+    pub fn apply(&self, secret: usize) -> usize {
+        self.columns
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| secret & (1 << i) != 0)
+            .fold(0, |image, (_, column)| image ^ column)
+    }
+
+    // self after other
+    fn compose(&self, other: &Self) -> Self {
+        BitMatrix {
+            columns: other.columns.map(|column| self.apply(column)),
+        }
+    }
+
+    // Exponentiation by squaring: O(log n) compositions
+    pub fn pow(&self, mut n: usize) -> Self {
+        let mut result = BitMatrix::identity();
+        let mut base = self.clone();
+
+        while n > 0 {
+            if n % 2 == 1 {
+                result = result.compose(&base);
+            }
+            base = base.compose(&base);
+            n /= 2;
+        }
+
+        result
+    }
+
+    // Gaussian elimination on the columns
+    pub fn rank(&self) -> usize {
+        let mut columns = self.columns.to_vec();
+        let mut rank = 0;
+
+        for bit in 0..SECRET_BITS {
+            let Some(pivot) = (rank..columns.len()).find(|i| columns[*i] & (1 << bit) != 0) else {
+                continue;
+            };
+            columns.swap(rank, pivot);
+            for i in 0..columns.len() {
+                if i != rank && columns[i] & (1 << bit) != 0 {
+                    columns[i] ^= columns[rank];
+                }
+            }
+            rank += 1;
+        }
+
+        rank
+    }
+
+    pub fn is_invertible(&self) -> bool {
+        self.rank() == SECRET_BITS
+    }
+}
@@ -1,6 +1,14 @@
-use std::collections::{HashMap, HashSet};
+use std::thread;
 
-use crate::read_input;
+use bit_matrix::BitMatrix;
+
+use crate::{option_value, read_input};
+
+mod bit_matrix;
+
+const SECRET_BITS: usize = 24;
+const SECRET_MASK: usize = (1 << SECRET_BITS) - 1;
+const NB_GENERATED_SECRETS: usize = 2000;
 
 fn read_initial_secret_numbers() -> Vec<usize> {
     read_input(2024, 22)
@@ -13,19 +21,54 @@ fn read_initial_secret_numbers() -> Vec<usize> {
 fn generate_next_secret(secret: usize) -> usize {
     let mut s1 = secret << 6;
     s1 ^= secret;
-    s1 &= (1 << 24) - 1;
+    s1 &= SECRET_MASK;
 
     let mut s2 = s1 >> 5;
     s2 ^= s1;
-    s2 &= (1 << 24) - 1;
+    s2 &= SECRET_MASK;
 
     let mut s3 = s2 << 11;
     s3 ^= s2;
-    s3 &= (1 << 24) - 1;
+    s3 &= SECRET_MASK;
 
     s3
 }
 
+// Solves s = x ^ (x << shift) for x: each pass recovers `shift` more low bits
+fn undo_xorshift_left(s: usize, shift: usize) -> usize {
+    let mut x = s;
+    for _ in 0..SECRET_BITS / shift {
+        x = (s ^ (x << shift)) & SECRET_MASK;
+    }
+    x
+}
+
+// Solves s = x ^ (x >> shift) for x: each pass recovers `shift` more high bits
+fn undo_xorshift_right(s: usize, shift: usize) -> usize {
+    let mut x = s;
+    for _ in 0..SECRET_BITS / shift {
+        x = s ^ (x >> shift);
+    }
+    x
+}
+
+// The secret the given one was generated from
+fn inverse_secret(secret: usize) -> usize {
+    let s2 = undo_xorshift_left(secret, 11);
+    let s1 = undo_xorshift_right(s2, 5);
+    undo_xorshift_left(s1, 6)
+}
+
+// Each generation step only shifts, masks and XORs, so it's linear over GF(2)
+// and n steps are a single matrix, computed in O(log n)
+fn jump_ahead_matrix(nb: usize) -> BitMatrix {
+    BitMatrix::from_linear_map(generate_next_secret).pow(nb)
+}
+
+fn jump_back_matrix(nb: usize) -> BitMatrix {
+    BitMatrix::from_linear_map(inverse_secret).pow(nb)
+}
+
 fn generate_next_secrets(initial_secret: usize, nb: usize) -> usize {
     let mut secret = initial_secret;
     for _ in 0..nb {
@@ -36,77 +79,151 @@ fn generate_next_secrets(initial_secret: usize, nb: usize) -> usize {
 
 pub fn run_part_1() {
     let initial_secret_numbers = read_initial_secret_numbers();
+    let jump_ahead = jump_ahead_matrix(NB_GENERATED_SECRETS);
 
     let sum = initial_secret_numbers
         .iter()
-        .map(|secret| generate_next_secrets(*secret, 2000))
+        .map(|secret| jump_ahead.apply(*secret))
         .sum::<usize>();
 
     println!("{:?}", sum);
 }
 
+// Checks the generation step is a bijection of the 24 bits secrets, and that the inverse
+// and the jump ahead agree with stepping one secret at a time
+pub fn run_check() {
+    let step = BitMatrix::from_linear_map(generate_next_secret);
+    assert!(step.is_invertible());
+
+    let mut is_generated = vec![false; 1 << SECRET_BITS];
+    for secret in 0..=SECRET_MASK {
+        let next = generate_next_secret(secret);
+        assert!(!is_generated[next], "{} is generated twice", next);
+        is_generated[next] = true;
+        assert_eq!(inverse_secret(next), secret);
+    }
+
+    let jump_ahead = jump_ahead_matrix(NB_GENERATED_SECRETS);
+    let jump_back = jump_back_matrix(NB_GENERATED_SECRETS);
+    for secret in read_initial_secret_numbers() {
+        let generated = generate_next_secrets(secret, NB_GENERATED_SECRETS);
+        assert_eq!(jump_ahead.apply(secret), generated);
+        assert_eq!(jump_back.apply(generated), secret);
+    }
+
+    println!("The secret generation is a bijection");
+}
+
+// Which initial secret gives SECRET after N generations, 2000 by default
+pub fn run_find_seed(options: &[String]) {
+    let secret = options
+        .first()
+        .and_then(|secret| secret.parse::<usize>().ok())
+        .filter(|secret| *secret <= SECRET_MASK)
+        .expect("seed expects a secret below 2^24");
+    let nb = options.get(1).map_or(NB_GENERATED_SECRETS, |nb| {
+        nb.parse::<usize>()
+            .expect("The number of generations is a number")
+    });
+
+    println!("{}", jump_back_matrix(nb).apply(secret));
+}
+
 type Sequence = (isize, isize, isize, isize);
 
-fn generate_buyer_sequences(initial_secret: usize) -> HashMap<Sequence, usize> {
-    let mut sequences: HashMap<Sequence, usize> = HashMap::new();
+// A price change is in -9..=9, so 4 changes in a row index a dense array of 19^4 cells
+const NB_CHANGE_VALUES: usize = 19;
+const NB_SEQUENCES: usize = NB_CHANGE_VALUES.pow(4);
 
-    let mut secret = initial_secret;
-    let mut previous_price = (secret % 10) as isize;
+fn decode_sequence(index: usize) -> Sequence {
+    let change = |position: u32| {
+        (index / NB_CHANGE_VALUES.pow(3 - position) % NB_CHANGE_VALUES) as isize - 9
+    };
+    (change(0), change(1), change(2), change(3))
+}
 
-    let mut changes = [0isize; 4];
+#[derive(Debug)]
+pub struct BestSequence {
+    pub sequence: Sequence,
+    pub bananas: usize,
+}
 
-    // valid sequence contains 4 digits, so simply compute the changes for the first 3 prices
-    for change in changes.iter_mut().take(3) {
+// Adds to each sequence the price the buyer sells at the first time the sequence shows up
+// `seen` is only a scratch bitset, reset for each buyer
+fn accumulate_buyer_bananas(initial_secret: usize, bananas: &mut [usize], seen: &mut [u64]) {
+    seen.fill(0);
+
+    let mut secret = initial_secret;
+    let mut previous_price = secret % 10;
+    let mut index = 0;
+
+    for nb_changes in 1..=NB_GENERATED_SECRETS {
         secret = generate_next_secret(secret);
-        let digit = (secret % 10) as isize;
-        *change = digit - previous_price;
-        previous_price = digit;
+        let price = secret % 10;
+        // sliding window: the oldest change goes away with the modulo
+        index = (index * NB_CHANGE_VALUES + price + 9 - previous_price) % NB_SEQUENCES;
+        previous_price = price;
+
+        // valid sequence contains 4 changes
+        if nb_changes < 4 || seen[index / 64] & (1 << (index % 64)) != 0 {
+            continue;
+        }
+        seen[index / 64] |= 1 << (index % 64);
+        bananas[index] += price;
     }
+}
 
-    for _ in 4..2000 {
-        secret = generate_next_secret(secret);
-        let digit = (secret % 10) as isize;
-        changes[3] = digit - previous_price;
-        previous_price = digit;
-
-        let sequence = (changes[0], changes[1], changes[2], changes[3]);
-        // keep the price for the first occurence of each sequence
-        sequences
-            .entry(sequence)
-            .or_insert_with(|| previous_price.try_into().unwrap());
-
-        // sliding window, only keep the last 3 prices
-        // shift values for next loop iteration
-        // changes[3] will be set with next price on next loop iteration
-        changes[0] = changes[1];
-        changes[1] = changes[2];
-        changes[2] = changes[3];
+fn accumulate_bananas(initial_secrets: &[usize]) -> Vec<usize> {
+    let mut bananas = vec![0; NB_SEQUENCES];
+    let mut seen = vec![0; NB_SEQUENCES.div_ceil(64)];
+
+    for secret in initial_secrets {
+        accumulate_buyer_bananas(*secret, &mut bananas, &mut seen);
     }
 
-    sequences
+    bananas
 }
 
-pub fn run_part_2() {
-    let buyers_initial_secret_numbers = read_initial_secret_numbers();
+// Buyers are split between the threads, each with its own accumulator, summed at the end
+pub fn find_best_sequence(initial_secrets: &[usize], nb_threads: usize) -> BestSequence {
+    let chunk_size = initial_secrets.len().div_ceil(nb_threads.max(1)).max(1);
+
+    let bananas = thread::scope(|scope| {
+        initial_secrets
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(|| accumulate_bananas(chunk)))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .reduce(|mut total, bananas| {
+                total.iter_mut().zip(bananas).for_each(|(t, b)| *t += b);
+                total
+            })
+            .unwrap_or_else(|| vec![0; NB_SEQUENCES])
+    });
 
-    let mut unique_sequences = HashSet::<Sequence>::new();
-    let mut buyers_sequences = Vec::new();
+    let (index, bananas) = bananas
+        .into_iter()
+        .enumerate()
+        .max_by_key(|(index, bananas)| (*bananas, std::cmp::Reverse(*index)))
+        .unwrap();
 
-    buyers_initial_secret_numbers.iter().for_each(|secret| {
-        let sequences = generate_buyer_sequences(*secret);
-        unique_sequences.extend(sequences.keys());
-        buyers_sequences.push(sequences);
-    });
+    BestSequence {
+        sequence: decode_sequence(index),
+        bananas,
+    }
+}
 
-    let max_bananas = unique_sequences
-        .iter()
-        .map(|sequence| {
-            buyers_sequences
-                .iter()
-                .map(|buyer_sequences| buyer_sequences.get(sequence).unwrap_or(&0))
-                .sum::<usize>()
-        })
-        .max();
-
-    println!("{:?}", max_bananas);
+// --threads N splits the buyers between N threads, one per core by default
+pub fn run_part_2(options: &[String]) {
+    let buyers_initial_secret_numbers = read_initial_secret_numbers();
+    let nb_threads = option_value(options, "--threads").map_or_else(
+        || thread::available_parallelism().map_or(1, |n| n.get()),
+        |nb_threads| nb_threads.parse().expect("--threads expects a number"),
+    );
+
+    let best_sequence = find_best_sequence(&buyers_initial_secret_numbers, nb_threads);
+
+    println!("{:?}", best_sequence.sequence);
+    println!("{}", best_sequence.bananas);
 }
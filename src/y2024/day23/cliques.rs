@@ -0,0 +1,129 @@
+use crate::graph::{NodeSet, UndirectedGraph};
+
+// Bron–Kerbosch with pivoting: the neighbours of the pivot are left to the deeper calls
+fn bron_kerbosch(
+    graph: &UndirectedGraph,
+    r: &mut Vec<usize>,
+    mut p: NodeSet,
+    mut x: NodeSet,
+    cliques: &mut Vec<Vec<usize>>,
+) {
+    if p.is_empty() {
+        if x.is_empty() {
+            let mut clique = r.clone();
+            clique.sort();
+            cliques.push(clique);
+        }
+        return;
+    }
+
+    // the pivot covering most of the candidates leaves the fewest branches
+    let pivot = p
+        .union(&x)
+        .iter()
+        .max_by_key(|node| p.intersection_len(graph.neighbours(*node)))
+        .unwrap();
+
+    for v in p.difference(graph.neighbours(pivot)).iter() {
+        let neighbours = graph.neighbours(v);
+
+        r.push(v);
+        bron_kerbosch(
+            graph,
+            r,
+            p.intersection(neighbours),
+            x.intersection(neighbours),
+            cliques,
+        );
+        r.pop();
+
+        p.remove(v);
+        x.insert(v);
+    }
+}
+
+// Nodes by repeatedly taking the one with the fewest remaining neighbours
+fn degeneracy_ordering(graph: &UndirectedGraph) -> Vec<usize> {
+    let mut remaining = graph.nodes();
+    let mut ordering = Vec::with_capacity(graph.len());
+
+    while let Some(node) = remaining
+        .iter()
+        .min_by_key(|node| graph.neighbours(*node).intersection_len(&remaining))
+    {
+        remaining.remove(node);
+        ordering.push(node);
+    }
+
+    ordering
+}
+
+// Every maximal clique as sorted node ids, in no particular order
+// Following the degeneracy ordering keeps the candidate sets of the outer calls small
+pub fn find_maximal_cliques(graph: &UndirectedGraph) -> Vec<Vec<usize>> {
+    let mut cliques = Vec::new();
+    let mut visited = NodeSet::new(graph.len());
+
+    for node in degeneracy_ordering(graph) {
+        let neighbours = graph.neighbours(node);
+        let p = neighbours.difference(&visited);
+        let x = neighbours.intersection(&visited);
+
+        bron_kerbosch(graph, &mut vec![node], p, x, &mut cliques);
+        visited.insert(node);
+    }
+
+    cliques
+}
+
+// Largest clique as node names, sorted; the first one alphabetically if several have the largest size
+pub fn find_maximum_clique(graph: &UndirectedGraph) -> Vec<&str> {
+    find_maximal_cliques(graph)
+        .into_iter()
+        .map(|clique| {
+            let mut names = graph.names(&clique);
+            names.sort();
+            names
+        })
+        .max_by(|a, b| a.len().cmp(&b.len()).then_with(|| b.cmp(a)))
+        .unwrap_or_default()
+}
+
+// Each clique is built in increasing id order, so it's only found once
+fn extend_clique(
+    graph: &UndirectedGraph,
+    clique: &mut Vec<usize>,
+    candidates: &NodeSet,
+    k: usize,
+    cliques: &mut Vec<Vec<usize>>,
+) {
+    if clique.len() == k {
+        cliques.push(clique.clone());
+        return;
+    }
+
+    for node in candidates.iter() {
+        if clique.last().is_some_and(|last| node <= *last) {
+            continue;
+        }
+
+        clique.push(node);
+        extend_clique(
+            graph,
+            clique,
+            &candidates.intersection(graph.neighbours(node)),
+            k,
+            cliques,
+        );
+        clique.pop();
+    }
+}
+
+// Every clique of exactly k nodes as sorted node ids, maximal or not
+pub fn find_k_cliques(graph: &UndirectedGraph, k: usize) -> Vec<Vec<usize>> {
+    let mut cliques = Vec::new();
+
+    extend_clique(graph, &mut vec![], &graph.nodes(), k, &mut cliques);
+
+    cliques
+}
@@ -1,111 +1,64 @@
-use std::collections::{HashMap, HashSet};
+use std::fs;
 
-use crate::read_input;
+use cliques::{find_k_cliques, find_maximum_clique};
 
-type Graph = HashMap<String, HashSet<String>>;
+use crate::{graph::UndirectedGraph, option_value, read_input};
 
-fn build_graph() -> Graph {
-    let mut graph: Graph = HashMap::new();
+mod cliques;
 
-    read_input(2024, 23)
-        .unwrap()
-        .map_while(Result::ok)
-        .map(|line| {
-            let (n1, n2) = line.split_once("-").unwrap();
-            (n1.to_string(), n2.to_string())
-        })
-        .for_each(|(n1, n2)| {
-            graph.entry(n1.clone()).or_default().insert(n2.clone());
-            graph.entry(n2).or_default().insert(n1);
-        });
+const DOT_FILE: &str = "src/y2024/day23/network.dot";
 
-    graph
+fn build_graph() -> UndirectedGraph {
+    let lines = read_input(2024, 23).unwrap().map_while(Result::ok);
+
+    UndirectedGraph::parse_edge_list(lines).unwrap_or_else(|error| panic!("{}", error))
 }
 
-pub fn run_part_1() {
+// --clique-size K counts the groups of K computers instead of 3
+pub fn run_part_1(options: &[String]) {
     let graph = build_graph();
+    let clique_size = option_value(options, "--clique-size").map_or(3, |clique_size| {
+        clique_size.parse().expect("--clique-size expects a number")
+    });
 
-    let mut unique_sets = HashSet::new();
-
-    for a in graph.keys() {
-        if let Some(a_children) = graph.get(a) {
-            for b in a_children {
-                if let Some(b_children) = graph.get(b) {
-                    for c in b_children {
-                        if let Some(c_children) = graph.get(c) {
-                            if c_children.contains(a) {
-                                let mut set = [a, b, c].to_vec();
-                                set.sort();
-                                unique_sets.insert(set);
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    let count = unique_sets
+    let count = find_k_cliques(&graph, clique_size)
         .iter()
-        .filter(|set| set.iter().any(|v| v.starts_with("t")))
+        .filter(|set| set.iter().any(|v| graph.name(*v).starts_with("t")))
         .count();
 
     println!("{}", count);
 }
 
-fn bron_kerbosch(
-    graph: &Graph,
-    r: &mut HashSet<String>,
-    p: &mut HashSet<String>,
-    x: &mut HashSet<String>,
-    max_clique_size: usize,
-) {
-    if p.is_empty() && x.is_empty() {
-        if r.len() == max_clique_size {
-            let mut values: Vec<String> = r.iter().cloned().collect();
-            values.sort();
-            println!("Found maximal clique: {:?}", values.join(","));
-        }
-    } else {
-        for v in p.clone() {
-            r.insert(v.clone());
-
-            let neighbors = graph.get(&v).unwrap();
-            let mut new_p = HashSet::new();
-            for node in p.iter() {
-                if neighbors.contains(node) {
-                    new_p.insert(node.clone());
-                }
-            }
-
-            let mut new_x = HashSet::new();
-            for node in x.iter() {
-                if neighbors.contains(node) {
-                    new_x.insert(node.clone());
-                }
-            }
-
-            bron_kerbosch(graph, r, &mut new_p, &mut new_x, max_clique_size);
-
-            r.remove(&v);
-            p.remove(&v);
-            x.insert(v);
-        }
-    }
-}
-
+// The LAN party password: the computers of the largest clique, sorted and comma joined
 pub fn run_part_2() {
     let graph = build_graph();
 
-    let max_clique_size = graph.values().fold(0, |acc, v| acc.max(v.len()));
+    let password = find_maximum_clique(&graph).join(",");
 
-    let mut r = HashSet::new();
-    let mut x = HashSet::new();
-    let mut p = HashSet::new();
+    println!("{}", password);
+}
+
+// Size, degrees and components of the network; --dot also writes it for Graphviz
+pub fn run_stats(options: &[String]) {
+    let graph = build_graph();
 
-    for node in graph.keys() {
-        p.insert(node.clone());
+    println!("{} computers, {} links", graph.len(), graph.nb_edges());
+    if let Some(stats) = graph.degree_stats() {
+        println!(
+            "Degree: min {}, max {}, mean {:.2}",
+            stats.min, stats.max, stats.mean
+        );
     }
 
-    bron_kerbosch(&graph, &mut r, &mut p, &mut x, max_clique_size);
+    let components = graph.connected_components();
+    println!(
+        "{} connected components, the largest has {} computers",
+        components.len(),
+        components.first().map_or(0, Vec::len)
+    );
+
+    if options.iter().any(|option| option == "--dot") {
+        fs::write(DOT_FILE, graph.to_dot()).expect("Failed to write the graph");
+        println!("{}", DOT_FILE);
+    }
 }
@@ -0,0 +1,267 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap},
+};
+
+use super::disk::{Disk, Segment};
+
+// `on_step` is called with the disk after each move
+pub trait CompactionStrategy {
+    fn name(&self) -> &str;
+    fn compact(&self, disk: &mut Disk, on_step: &mut dyn FnMut(&Disk));
+}
+
+pub fn parse_strategy(name: &str) -> Option<Box<dyn CompactionStrategy>> {
+    all_strategies()
+        .into_iter()
+        .find(|strategy| strategy.name() == name)
+}
+
+pub fn all_strategies() -> Vec<Box<dyn CompactionStrategy>> {
+    vec![
+        Box::new(BlockByBlock),
+        Box::new(WholeFile(Fit::First)),
+        Box::new(WholeFile(Fit::Best)),
+        Box::new(WholeFile(Fit::Worst)),
+        Box::new(Defragment),
+    ]
+}
+
+// Part 1: moves blocks one at a time from the end of the disk to the leftmost free block
+pub struct BlockByBlock;
+
+impl CompactionStrategy for BlockByBlock {
+    fn name(&self) -> &str {
+        "block"
+    }
+
+    fn compact(&self, disk: &mut Disk, on_step: &mut dyn FnMut(&Disk)) {
+        let (mut free, mut last) = (0, disk.blocks().len().saturating_sub(1));
+
+        loop {
+            while free < last && disk.blocks()[free].is_some() {
+                free += 1;
+            }
+            while free < last && disk.blocks()[last].is_none() {
+                last -= 1;
+            }
+            if free >= last {
+                break;
+            }
+
+            disk.swap_blocks(free, last);
+            on_step(disk);
+        }
+    }
+}
+
+// Which gap a whole file goes to, among the gaps left of it and large enough
+pub enum Fit {
+    // the leftmost one, as in part 2
+    First,
+    // the smallest one
+    Best,
+    // the largest one
+    Worst,
+}
+
+// Moves whole files, from the highest id down, never splitting them
+// One min-heap of gap starts per gap size: finding the gap is a look at the heap tops of
+// the sizes large enough, at most 9 on a disk fresh from its map
+pub struct WholeFile(pub Fit);
+
+impl CompactionStrategy for WholeFile {
+    fn name(&self) -> &str {
+        match self.0 {
+            Fit::First => "first-fit",
+            Fit::Best => "best-fit",
+            Fit::Worst => "worst-fit",
+        }
+    }
+
+    fn compact(&self, disk: &mut Disk, on_step: &mut dyn FnMut(&Disk)) {
+        let mut gaps_by_size: BTreeMap<usize, BinaryHeap<Reverse<usize>>> = BTreeMap::new();
+        for gap in disk.gap_segments() {
+            gaps_by_size
+                .entry(gap.len)
+                .or_default()
+                .push(Reverse(gap.start));
+        }
+
+        let files = disk.file_segments();
+        for id in (0..files.len()).rev() {
+            let file = files[id];
+
+            // gaps right of the file are never used again, as files only move left
+            let candidates = gaps_by_size
+                .range(file.len.max(1)..)
+                .filter_map(|(len, starts)| starts.peek().map(|Reverse(start)| (*len, *start)))
+                .filter(|(_, start)| *start < file.start);
+            let gap = match self.0 {
+                Fit::First => candidates.min_by_key(|(_, start)| *start),
+                Fit::Best => candidates.min(),
+                Fit::Worst => candidates.max_by_key(|(len, start)| (*len, Reverse(*start))),
+            };
+            let Some((gap_len, gap_start)) = gap else {
+                continue;
+            };
+
+            let starts = gaps_by_size.get_mut(&gap_len).unwrap();
+            starts.pop();
+            if starts.is_empty() {
+                gaps_by_size.remove(&gap_len);
+            }
+            if gap_len > file.len {
+                gaps_by_size
+                    .entry(gap_len - file.len)
+                    .or_default()
+                    .push(Reverse(gap_start + file.len));
+            }
+
+            disk.move_file(id, file, gap_start);
+            on_step(disk);
+        }
+    }
+}
+
+// Packs the files at the start of the disk without splitting them, trying to move as few
+// files as possible: files already inside the packed area stay where they are, and the
+// others fill the holes left between them, largest first, each in the smallest hole it fits
+// Exact packing is NP-hard, so when some hole can't be filled exactly, the smaller file next
+// to it is moved too, merging the hole with its neighbours, until everything fits
+pub struct Defragment;
+
+impl Defragment {
+    // Where each moved file goes, by file id
+    fn plan(files: &[Segment]) -> HashMap<usize, usize> {
+        let packed_len = files.iter().map(|file| file.len).sum::<usize>();
+        let mut staying = files
+            .iter()
+            .map(|file| file.len > 0 && file.start + file.len <= packed_len)
+            .collect::<Vec<_>>();
+
+        loop {
+            let mut staying_files = (0..files.len())
+                .filter(|id| staying[*id])
+                .collect::<Vec<_>>();
+            staying_files.sort_by_key(|id| files[*id].start);
+
+            // holes of the packed area, with the staying files on each side
+            let mut holes = vec![];
+            let mut end = (0, None);
+            for id in staying_files
+                .iter()
+                .map(|id| Some(*id))
+                .chain(std::iter::once(None))
+            {
+                let start = id.map_or(packed_len, |id| files[id].start);
+                if start > end.0 {
+                    let hole = Segment {
+                        start: end.0,
+                        len: start - end.0,
+                    };
+                    holes.push((hole, [end.1, id]));
+                }
+                if let Some(id) = id {
+                    end = (files[id].start + files[id].len, Some(id));
+                }
+            }
+
+            // free pieces of the holes by size, with the hole they're part of
+            let mut pieces: BTreeMap<usize, BTreeSet<(usize, usize)>> = BTreeMap::new();
+            for (index, (hole, _)) in holes.iter().enumerate() {
+                pieces
+                    .entry(hole.len)
+                    .or_default()
+                    .insert((hole.start, index));
+            }
+
+            let mut moved_files = (0..files.len())
+                .filter(|id| !staying[*id] && files[*id].len > 0)
+                .collect::<Vec<_>>();
+            moved_files.sort_by_key(|id| (Reverse(files[*id].len), *id));
+
+            let mut targets = HashMap::new();
+            for id in moved_files {
+                let len = files[id].len;
+                let Some((&piece_len, starts)) = pieces.range_mut(len..).next() else {
+                    continue;
+                };
+                let (start, hole) = starts.pop_first().unwrap();
+                if starts.is_empty() {
+                    pieces.remove(&piece_len);
+                }
+                if piece_len > len {
+                    pieces
+                        .entry(piece_len - len)
+                        .or_default()
+                        .insert((start + len, hole));
+                }
+                targets.insert(id, start);
+            }
+
+            if pieces.is_empty() {
+                return targets;
+            }
+
+            for (_, hole) in pieces.into_values().flatten() {
+                let evicted = holes[hole]
+                    .1
+                    .into_iter()
+                    .flatten()
+                    .min_by_key(|id| files[*id].len)
+                    .expect("A hole of the whole packed area is always filled");
+                staying[evicted] = false;
+            }
+        }
+    }
+}
+
+impl CompactionStrategy for Defragment {
+    fn name(&self) -> &str {
+        "defragment"
+    }
+
+    // A file only moves once its target blocks are free; when every remaining file waits on
+    // another one, one of them is lifted off the disk, as if kept aside in memory
+    fn compact(&self, disk: &mut Disk, on_step: &mut dyn FnMut(&Disk)) {
+        let files = disk.file_segments();
+        let mut pending = Self::plan(&files).into_iter().collect::<Vec<_>>();
+        pending.sort();
+        let mut lifted = vec![false; files.len()];
+
+        while !pending.is_empty() {
+            let ready = pending.iter().position(|(id, to)| {
+                // the file may overlap its own blocks
+                disk.blocks()[*to..*to + files[*id].len]
+                    .iter()
+                    .all(|block| block.is_none() || *block == Some(*id))
+            });
+
+            match ready {
+                Some(index) => {
+                    let (id, to) = pending.remove(index);
+                    if lifted[id] {
+                        let target = Segment {
+                            start: to,
+                            len: files[id].len,
+                        };
+                        disk.place_file(id, target);
+                    } else {
+                        disk.move_file(id, files[id], to);
+                    }
+                    on_step(disk);
+                }
+                None => {
+                    // some pending file is still on the disk, or its target would be free
+                    let (id, _) = *pending
+                        .iter()
+                        .find(|(id, _)| !lifted[*id])
+                        .expect("Targets of lifted files are only held by pending files");
+                    disk.free_blocks(files[id]);
+                    lifted[id] = true;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+#[derive(Clone, Copy)]
+pub struct Segment {
+    pub start: usize,
+    pub len: usize,
+}
+
+pub struct FragmentationStats {
+    // free blocks sitting before the last file block
+    pub nb_inner_free_blocks: usize,
+    pub nb_inner_gaps: usize,
+    pub largest_inner_gap: usize,
+    // files spread over more than one run of blocks
+    pub nb_split_files: usize,
+}
+
+// Block-level disk: each block holds the id of its file, or nothing when free
+pub struct Disk {
+    blocks: Vec<Option<usize>>,
+    nb_files: usize,
+}
+
+impl Disk {
+    // The dense disk map alternates file and free space lengths, starting with a file
+    pub fn parse(disk_map: &str) -> Disk {
+        let mut blocks = Vec::new();
+        let mut nb_files = 0;
+
+        for (i, c) in disk_map.trim().chars().enumerate() {
+            let len = c.to_digit(10).expect("The disk map only contains digits") as usize;
+
+            if i % 2 == 0 {
+                blocks.extend(std::iter::repeat_n(Some(nb_files), len));
+                nb_files += 1;
+            } else {
+                blocks.extend(std::iter::repeat_n(None, len));
+            }
+        }
+
+        Disk { blocks, nb_files }
+    }
+
+    pub fn compute_checksum(&self) -> usize {
+        self.blocks
+            .iter()
+            .enumerate()
+            .filter_map(|(position, id)| id.map(|id| position * id))
+            .sum()
+    }
+
+    // Same layout as the puzzle text: free blocks as dots, file ids past 9 only keep their last digit
+    pub fn render(&self) -> String {
+        self.blocks
+            .iter()
+            .map(|block| match block {
+                Some(id) => char::from_digit((id % 10) as u32, 10).unwrap(),
+                None => '.',
+            })
+            .collect()
+    }
+
+    pub fn blocks(&self) -> &[Option<usize>] {
+        &self.blocks
+    }
+
+    pub fn swap_blocks(&mut self, a: usize, b: usize) {
+        self.blocks.swap(a, b);
+    }
+
+    // Runs of consecutive blocks holding the same file, or free
+    fn runs(&self) -> Vec<(Option<usize>, Segment)> {
+        let mut runs: Vec<(Option<usize>, Segment)> = Vec::new();
+
+        for (position, block) in self.blocks.iter().enumerate() {
+            match runs.last_mut() {
+                Some((id, segment)) if id == block => segment.len += 1,
+                _ => runs.push((
+                    *block,
+                    Segment {
+                        start: position,
+                        len: 1,
+                    },
+                )),
+            }
+        }
+
+        runs
+    }
+
+    // Indexed by file id, only meant for files that aren't split
+    pub fn file_segments(&self) -> Vec<Segment> {
+        let mut files = vec![Segment { start: 0, len: 0 }; self.nb_files];
+        for (id, segment) in self.runs() {
+            if let Some(id) = id {
+                if files[id].len == 0 {
+                    files[id].start = segment.start;
+                }
+                files[id].len += segment.len;
+            }
+        }
+        files
+    }
+
+    pub fn gap_segments(&self) -> Vec<Segment> {
+        self.runs()
+            .into_iter()
+            .filter(|(id, _)| id.is_none())
+            .map(|(_, segment)| segment)
+            .collect()
+    }
+
+    // The file must fit in the free blocks at `to`, or overlap its own blocks
+    pub fn move_file(&mut self, id: usize, from: Segment, to: usize) {
+        self.free_blocks(from);
+        self.place_file(
+            id,
+            Segment {
+                start: to,
+                len: from.len,
+            },
+        );
+    }
+
+    pub fn free_blocks(&mut self, segment: Segment) {
+        self.blocks[segment.start..segment.start + segment.len].fill(None);
+    }
+
+    pub fn place_file(&mut self, id: usize, segment: Segment) {
+        self.blocks[segment.start..segment.start + segment.len].fill(Some(id));
+    }
+
+    pub fn fragmentation_stats(&self) -> FragmentationStats {
+        let runs = self.runs();
+        let inner_gaps = runs
+            .iter()
+            .rev()
+            .skip_while(|(id, _)| id.is_none())
+            .filter(|(id, _)| id.is_none())
+            .map(|(_, segment)| segment.len)
+            .collect::<Vec<_>>();
+
+        let mut nb_runs_by_file = vec![0; self.nb_files];
+        runs.iter()
+            .filter_map(|(id, _)| *id)
+            .for_each(|id| nb_runs_by_file[id] += 1);
+
+        FragmentationStats {
+            nb_inner_free_blocks: inner_gaps.iter().sum(),
+            nb_inner_gaps: inner_gaps.len(),
+            largest_inner_gap: inner_gaps.iter().max().copied().unwrap_or(0),
+            nb_split_files: nb_runs_by_file
+                .iter()
+                .filter(|nb_runs| **nb_runs > 1)
+                .count(),
+        }
+    }
+}
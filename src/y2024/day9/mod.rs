@@ -1,136 +1,77 @@
-use crate::read_input;
-
-#[derive(Debug)]
-struct MovedFile {
-    id: usize,
-    number: usize,
-}
-
-#[derive(Debug)]
-struct FileDiskPart {
-    // when file blocks get moved, these are replaced by free space blocks
-    // but these are different than input free space blocks
-    // Input = file_blocks, free_space_blocks
-    // Then = file_free_space_blocks, file_blocks, moved_here, free_space_blocks
-    pub file_free_space_blocks: usize,
-    pub file_blocks: usize,
-    pub moved_here: Vec<MovedFile>,
-    pub free_space_blocks: usize,
+use compaction::{
+    all_strategies, parse_strategy, BlockByBlock, CompactionStrategy, Fit, WholeFile,
+};
+use disk::Disk;
+
+use crate::{option_value, read_input};
+
+mod compaction;
+mod disk;
+
+// --map DISK_MAP uses the given disk map instead of the input
+fn read_disk_map(options: &[String]) -> String {
+    option_value(options, "--map").map_or_else(
+        || {
+            read_input(2024, 9)
+                .unwrap()
+                .map_while(Result::ok)
+                .collect::<Vec<String>>()
+                .join("")
+        },
+        String::from,
+    )
 }
 
-fn compute_checksum(files: Vec<FileDiskPart>) -> usize {
-    let mut multiplier = 0;
-    let mut checksum = 0;
-
-    for (id, file) in files.iter().enumerate() {
-        for _ in 0..file.file_free_space_blocks {
-            multiplier += 1;
-        }
-
-        for _ in 0..file.file_blocks {
-            checksum += multiplier * id;
-            multiplier += 1;
-        }
-
-        for moved in file.moved_here.iter() {
-            for _ in 0..moved.number {
-                checksum += multiplier * moved.id;
-                multiplier += 1;
-            }
-        }
+// --show prints the disk after each step, as in the puzzle text
+// --strategy NAME replaces the compaction rule of the part
+fn run(default_strategy: Box<dyn CompactionStrategy>, options: &[String]) {
+    let strategy = option_value(options, "--strategy").map_or(default_strategy, |name| {
+        parse_strategy(name)
+            .expect("--strategy expects block, first-fit, best-fit, worst-fit or defragment")
+    });
+    let is_show = options.iter().any(|option| option == "--show");
+
+    let mut disk = Disk::parse(&read_disk_map(options));
+    if is_show {
+        println!("{}", disk.render());
+    }
 
-        for _ in 0..file.free_space_blocks {
-            multiplier += 1;
+    strategy.compact(&mut disk, &mut |disk| {
+        if is_show {
+            println!("{}", disk.render());
         }
-    }
+    });
 
-    checksum
+    println!("{}", disk.compute_checksum());
 }
 
-fn read_files() -> Vec<FileDiskPart> {
-    let lines = read_input(2024, 9).unwrap();
-    let mut line: String = lines
-        .map_while(Result::ok)
-        .collect::<Vec<String>>()
-        .join("");
-    line.push('0');
-
-    let files: Vec<_> = line
-        .chars()
-        .collect::<Vec<char>>()
-        .chunks(2)
-        .map(|pair| FileDiskPart {
-            file_blocks: pair[0].to_digit(10).expect("") as usize,
-            free_space_blocks: pair[1].to_digit(10).expect("") as usize,
-            file_free_space_blocks: 0,
-            moved_here: Default::default(),
-        })
-        .collect();
-
-    files
+pub fn run_part_1(options: &[String]) {
+    run(Box::new(BlockByBlock), options);
 }
 
-pub fn run_part_2() {
-    let mut files = read_files();
-
-    for to_move_idx in (1..files.len()).rev() {
-        for candidate_idx in 0..to_move_idx {
-            let number_files = files[to_move_idx].file_blocks;
-
-            if number_files > 0 && files[candidate_idx].free_space_blocks >= number_files {
-                files[candidate_idx].moved_here.push(MovedFile {
-                    id: to_move_idx,
-                    number: number_files,
-                });
-                files[candidate_idx].free_space_blocks -= number_files;
-                files[to_move_idx].file_free_space_blocks += number_files;
-                files[to_move_idx].file_blocks = 0;
-            }
-        }
-    }
-
-    println!("{}", compute_checksum(files));
+pub fn run_part_2(options: &[String]) {
+    run(Box::new(WholeFile(Fit::First)), options);
 }
 
-pub fn run_part_1() {
-    let mut files = read_files();
-
-    let mut candidate_idx = 0;
-    let mut to_move_idx = files.len() - 1;
-
-    while candidate_idx < to_move_idx {
-        let can_move_all_to_candidate =
-            files[candidate_idx].free_space_blocks >= files[to_move_idx].file_blocks;
-
-        if can_move_all_to_candidate {
-            let number_files = files[to_move_idx].file_blocks;
-
-            files[candidate_idx].free_space_blocks -= number_files;
-            files[candidate_idx].moved_here.push(MovedFile {
-                id: to_move_idx,
-                number: number_files,
-            });
-
-            files[to_move_idx].file_blocks = 0;
-
-            to_move_idx -= 1;
-            if files[candidate_idx].free_space_blocks == 0 {
-                candidate_idx += 1;
-            }
-        } else {
-            let number_files = files[candidate_idx].free_space_blocks;
-
-            files[candidate_idx].free_space_blocks = 0;
-            files[candidate_idx].moved_here.push(MovedFile {
-                id: to_move_idx,
-                number: number_files,
-            });
-
-            files[to_move_idx].file_blocks -= number_files;
-
-            candidate_idx += 1;
-        }
+// Checksum, number of moves and fragmentation left by every strategy
+pub fn run_compare(options: &[String]) {
+    let disk_map = read_disk_map(options);
+
+    for strategy in all_strategies() {
+        let mut disk = Disk::parse(&disk_map);
+        let mut nb_moves = 0;
+        strategy.compact(&mut disk, &mut |_| nb_moves += 1);
+
+        let stats = disk.fragmentation_stats();
+        println!(
+            "{:<10} checksum {:>15}, {:>6} moves, {:>6} free blocks in {:>5} gaps (largest {}), {} split files",
+            strategy.name(),
+            disk.compute_checksum(),
+            nb_moves,
+            stats.nb_inner_free_blocks,
+            stats.nb_inner_gaps,
+            stats.largest_inner_gap,
+            stats.nb_split_files,
+        );
     }
-
-    println!("{}", compute_checksum(files));
 }